@@ -0,0 +1,23 @@
+//! Account and return data decoders
+//!
+//! Accounts are decoded with their Anchor discriminator checked, so data from
+//! the wrong account type is rejected rather than misread.
+
+use anchor_lang::prelude::*;
+use anchor_lang::AccountDeserialize;
+
+pub use snail_game::{
+    CheckpointLog, GameState, Market, ObservationBuffer, Position, Shell, Stake,
+};
+pub use snail_launch::{ContributorData, LaunchState};
+
+/// Decode the data of a `snail_game` or `snail_launch` account
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Decode the return data of a view instruction, such as the `GameStatus` of
+/// `get_game_status` or the `u64` of `check_current_market_cap`
+pub fn decode_return<T: AnchorDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_from_slice(data).map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+}
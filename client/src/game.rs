@@ -0,0 +1,905 @@
+//! Instruction builders for `snail_game`
+//!
+//! Builders derive every PDA themselves. Those that act on an existing game take
+//! its decoded `GameState` and read the LP accounts, mints, pools and the
+//! remaining accounts the program expects from it, so they stay in step with
+//! `add_pool`, `configure_supply` and `configure_freeze_accounts`. Owner-only
+//! instructions are signed by `game_state.owner`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::token_2022;
+use snail_game::{
+    accounts, instruction, CurveKind, GameState, MarketSide, Outcome, PoolConfig, SupplyMode,
+    TimingMode,
+};
+
+use crate::pda;
+
+/// Parameters of a new game, shared by `initialize` and `preview_game`
+#[derive(Clone, Debug)]
+pub struct GameParameters {
+    pub snail_start_stamp: i64, // Unix timestamp or slot, per timing_mode
+    pub snail_end_stamp: i64,
+    pub target_market_cap: u64, // In units of 10^-market_cap_decimals USDC
+    pub market_cap_decimals: u8,
+    pub curve: CurveKind,
+    pub twap_window: i64,
+    pub grace_period: i64,
+    pub outcome: Outcome,
+    pub timing_mode: TimingMode,
+}
+
+/// Optional accounts of `touch_snail` and `confirm_touch`
+/// The bounty and outcome vaults and mints are read from `GameState`; these are
+/// the caller's side of the payouts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TouchAccounts {
+    pub toucher_token_account: Option<Pubkey>, // Receives the bounty, owned by the paid toucher
+    pub bounty_token_program: Option<Pubkey>,
+    pub outcome_token_program: Option<Pubkey>, // Required once the outcome vault exists
+    pub penalty_token_account: Option<Pubkey>, // For TransferPenalty
+}
+
+/// Create a game priced from `pool` (`add_pool` adds the secondary ones)
+pub fn initialize(
+    owner: Pubkey,
+    game_id: u64,
+    usdc_mint: Pubkey,
+    snail_mint: Pubkey,
+    pool: &PoolConfig,
+    parameters: GameParameters,
+) -> Instruction {
+    let game_state = pda::game_state(game_id).0;
+    build(
+        accounts::Initialize {
+            game_state,
+            observations: pda::observations(&game_state).0,
+            usdc_lp: pool.usdc_vault,
+            snail_lp: pool.snail_vault,
+            snail_mint,
+            freeze_authority: pda::freeze_authority(game_id).0,
+            pool: pool_account(pool),
+            usdc_mint,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::Initialize {
+            game_id,
+            snail_start_stamp: parameters.snail_start_stamp,
+            snail_end_stamp: parameters.snail_end_stamp,
+            target_market_cap: parameters.target_market_cap,
+            market_cap_decimals: parameters.market_cap_decimals,
+            curve: parameters.curve,
+            twap_window: parameters.twap_window,
+            price_source: pool.price_source,
+            grace_period: parameters.grace_period,
+            outcome: parameters.outcome,
+            timing_mode: parameters.timing_mode,
+        },
+        vec![],
+    )
+}
+
+/// Dry run of `initialize`, returning the sampled curve
+pub fn preview_game(parameters: GameParameters) -> Instruction {
+    build(
+        accounts::PreviewGame {},
+        instruction::PreviewGame {
+            snail_start_stamp: parameters.snail_start_stamp,
+            snail_end_stamp: parameters.snail_end_stamp,
+            target_market_cap: parameters.target_market_cap,
+            market_cap_decimals: parameters.market_cap_decimals,
+            curve: parameters.curve,
+            twap_window: parameters.twap_window,
+            grace_period: parameters.grace_period,
+            outcome: parameters.outcome,
+            timing_mode: parameters.timing_mode,
+        },
+        vec![],
+    )
+}
+
+pub fn check_required_market_cap(game_id: u64, time_point: i64) -> Instruction {
+    build(
+        accounts::CheckRequiredMarketCap {
+            game_state: pda::game_state(game_id).0,
+        },
+        instruction::CheckRequiredMarketCap {
+            _game_id: game_id,
+            time_point,
+        },
+        vec![],
+    )
+}
+
+pub fn check_current_market_cap(game_state: &GameState) -> Instruction {
+    build(
+        accounts::CheckCurrentMarketCap {
+            game_state: pda::game_state(game_state.game_id).0,
+            usdc_lp: game_state.usdc_lp,
+            snail_lp: game_state.snail_lp,
+            snail_mint: game_state.snail_mint,
+            pool: primary_pool(game_state),
+            token_program: token_2022::ID,
+        },
+        instruction::CheckCurrentMarketCap {
+            _game_id: game_state.game_id,
+        },
+        market_cap_accounts(game_state),
+    )
+}
+
+pub fn touch_snail(game_state: &GameState, toucher: Pubkey, touch_accounts: TouchAccounts) -> Instruction {
+    build(
+        touch_snail_accounts(game_state, toucher, None, touch_accounts),
+        instruction::TouchSnail {
+            game_id: game_state.game_id,
+        },
+        touch_remaining_accounts(game_state),
+    )
+}
+
+/// Confirm the pending touch of `game_state.toucher`, who is paid the bounty;
+/// `toucher` only signs
+pub fn confirm_touch(game_state: &GameState, toucher: Pubkey, touch_accounts: TouchAccounts) -> Instruction {
+    build(
+        touch_snail_accounts(game_state, toucher, Some(game_state.toucher), touch_accounts),
+        instruction::ConfirmTouch {
+            game_id: game_state.game_id,
+        },
+        touch_remaining_accounts(game_state),
+    )
+}
+
+pub fn clear_touch(game_state: &GameState) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::ClearTouch {
+            game_state: address,
+            observations: pda::observations(&address).0,
+            usdc_lp: game_state.usdc_lp,
+            snail_lp: game_state.snail_lp,
+            snail_mint: game_state.snail_mint,
+            pool: primary_pool(game_state),
+        },
+        instruction::ClearTouch {
+            game_id: game_state.game_id,
+        },
+        market_cap_accounts(game_state),
+    )
+}
+
+/// Only the owner may pass `renounce_freeze_authority`
+pub fn declare_victory(game_state: &GameState, caller: Pubkey, renounce_freeze_authority: bool) -> Instruction {
+    build(
+        accounts::DeclareVictory {
+            game_state: pda::game_state(game_state.game_id).0,
+            snail_mint: game_state.snail_mint,
+            freeze_authority: pda::freeze_authority(game_state.game_id).0,
+            caller,
+            token_program: token_2022::ID,
+        },
+        instruction::DeclareVictory {
+            game_id: game_state.game_id,
+            renounce_freeze_authority,
+        },
+        vec![],
+    )
+}
+
+/// Pay touchers a fixed `amount`, or `bps` of the vault balance when non-zero
+pub fn initialize_bounty(
+    game_state: &GameState,
+    bounty_mint: Pubkey,
+    token_program: Pubkey,
+    amount: u64,
+    bps: u16,
+) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::InitializeBounty {
+            game_state: address,
+            owner: game_state.owner,
+            bounty_mint,
+            bounty_vault: pda::bounty_vault(&address).0,
+            token_program,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeBounty {
+            _game_id: game_state.game_id,
+            amount,
+            bps,
+        },
+        vec![],
+    )
+}
+
+pub fn initialize_outcome_vault(game_state: &GameState, outcome_mint: Pubkey, token_program: Pubkey) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::InitializeOutcomeVault {
+            game_state: address,
+            owner: game_state.owner,
+            outcome_mint,
+            outcome_vault: pda::outcome_vault(&address).0,
+            token_program,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeOutcomeVault {
+            _game_id: game_state.game_id,
+        },
+        vec![],
+    )
+}
+
+/// Burn `amount` SNAIL for a share of the released outcome vault
+pub fn claim_reward(
+    game_state: &GameState,
+    holder: Pubkey,
+    holder_snail_account: Pubkey,
+    holder_reward_account: Pubkey,
+    outcome_token_program: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::ClaimReward {
+            game_state: address,
+            snail_mint: game_state.snail_mint,
+            holder_snail_account,
+            outcome_vault: pda::outcome_vault(&address).0,
+            outcome_mint: game_state.outcome_mint,
+            holder_reward_account,
+            holder,
+            token_program: token_2022::ID,
+            outcome_token_program,
+        },
+        instruction::ClaimReward {
+            game_id: game_state.game_id,
+            amount,
+        },
+        vec![],
+    )
+}
+
+pub fn initialize_shell(game_state: &GameState, reward_mint: Pubkey, token_program: Pubkey) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::InitializeShell {
+            game_state: address,
+            shell: pda::shell(&address).0,
+            owner: game_state.owner,
+            reward_mint,
+            reward_vault: pda::shell_reward_vault(&address).0,
+            token_program,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeShell {
+            _game_id: game_state.game_id,
+        },
+        vec![],
+    )
+}
+
+/// Create `owner`'s stake account and stake vault
+pub fn open_stake(game_state: &GameState, owner: Pubkey) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    let stake = pda::stake(&address, &owner).0;
+    build(
+        accounts::OpenStake {
+            game_state: address,
+            shell: pda::shell(&address).0,
+            stake,
+            snail_mint: game_state.snail_mint,
+            stake_vault: pda::stake_vault(&stake).0,
+            owner,
+            token_program: token_2022::ID,
+            system_program: system_program::ID,
+        },
+        instruction::OpenStake {
+            _game_id: game_state.game_id,
+        },
+        vec![],
+    )
+}
+
+/// Stake what `owner` has transferred into their stake vault
+pub fn stake(game_id: u64, owner: Pubkey) -> Instruction {
+    let address = pda::game_state(game_id).0;
+    let stake = pda::stake(&address, &owner).0;
+    build(
+        accounts::StakeSnail {
+            game_state: address,
+            shell: pda::shell(&address).0,
+            stake,
+            stake_vault: pda::stake_vault(&stake).0,
+            owner,
+        },
+        instruction::Stake { _game_id: game_id },
+        vec![],
+    )
+}
+
+pub fn unstake(game_id: u64, owner: Pubkey, amount: u64) -> Instruction {
+    let address = pda::game_state(game_id).0;
+    let stake = pda::stake(&address, &owner).0;
+    build(
+        accounts::Unstake {
+            game_state: address,
+            shell: pda::shell(&address).0,
+            stake,
+            stake_vault: pda::stake_vault(&stake).0,
+            owner,
+            token_program: token_2022::ID,
+        },
+        instruction::Unstake {
+            _game_id: game_id,
+            amount,
+        },
+        vec![],
+    )
+}
+
+pub fn claim_shell_reward(
+    game_id: u64,
+    owner: Pubkey,
+    reward_mint: Pubkey,
+    owner_reward_account: Pubkey,
+    reward_token_program: Pubkey,
+) -> Instruction {
+    let address = pda::game_state(game_id).0;
+    build(
+        accounts::ClaimShellReward {
+            game_state: address,
+            shell: pda::shell(&address).0,
+            stake: pda::stake(&address, &owner).0,
+            reward_vault: pda::shell_reward_vault(&address).0,
+            reward_mint,
+            owner_reward_account,
+            owner,
+            reward_token_program,
+        },
+        instruction::ClaimShellReward { game_id },
+        vec![],
+    )
+}
+
+/// Return the shell rewards nobody can claim to the game owner
+pub fn sweep_shell_rewards(
+    game_state: &GameState,
+    reward_mint: Pubkey,
+    owner_reward_account: Pubkey,
+    reward_token_program: Pubkey,
+) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::SweepShellRewards {
+            game_state: address,
+            shell: pda::shell(&address).0,
+            reward_vault: pda::shell_reward_vault(&address).0,
+            reward_mint,
+            owner_reward_account,
+            owner: game_state.owner,
+            reward_token_program,
+        },
+        instruction::SweepShellRewards {
+            game_id: game_state.game_id,
+        },
+        vec![],
+    )
+}
+
+/// Hand `stake_owner`'s stake to the game's toucher
+pub fn slash_stake(game_state: &GameState, stake_owner: Pubkey) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    let stake = pda::stake(&address, &stake_owner).0;
+    build(
+        accounts::SlashStake {
+            game_state: address,
+            shell: pda::shell(&address).0,
+            stake,
+            stake_vault: pda::stake_vault(&stake).0,
+            toucher: game_state.toucher,
+            token_program: token_2022::ID,
+        },
+        instruction::SlashStake {
+            _game_id: game_state.game_id,
+        },
+        vec![],
+    )
+}
+
+/// `token_program` is the token program of the game's USDC mint
+pub fn initialize_market(game_state: &GameState, token_program: Pubkey, cutoff: i64) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::InitializeMarket {
+            game_state: address,
+            market: pda::market(&address).0,
+            usdc_mint: game_state.usdc_mint,
+            market_vault: pda::market_vault(&address).0,
+            owner: game_state.owner,
+            token_program,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeMarket {
+            _game_id: game_state.game_id,
+            cutoff,
+        },
+        vec![],
+    )
+}
+
+pub fn open_position(game_id: u64, owner: Pubkey) -> Instruction {
+    let address = pda::game_state(game_id).0;
+    let market = pda::market(&address).0;
+    build(
+        accounts::OpenPosition {
+            game_state: address,
+            market,
+            position: pda::position(&market, &owner).0,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::OpenPosition { _game_id: game_id },
+        vec![],
+    )
+}
+
+pub fn deposit_prediction(
+    game_state: &GameState,
+    owner: Pubkey,
+    owner_usdc_account: Pubkey,
+    token_program: Pubkey,
+    side: MarketSide,
+    amount: u64,
+) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    let market = pda::market(&address).0;
+    build(
+        accounts::DepositPrediction {
+            game_state: address,
+            observations: pda::observations(&address).0,
+            usdc_lp: game_state.usdc_lp,
+            snail_lp: game_state.snail_lp,
+            snail_mint: game_state.snail_mint,
+            pool: primary_pool(game_state),
+            market,
+            position: pda::position(&market, &owner).0,
+            market_vault: pda::market_vault(&address).0,
+            usdc_mint: game_state.usdc_mint,
+            owner_usdc_account,
+            owner,
+            token_program,
+        },
+        instruction::DepositPrediction {
+            _game_id: game_state.game_id,
+            side,
+            amount,
+        },
+        market_cap_accounts(game_state),
+    )
+}
+
+pub fn claim_prediction(
+    game_state: &GameState,
+    owner: Pubkey,
+    owner_usdc_account: Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    let market = pda::market(&address).0;
+    build(
+        accounts::ClaimPrediction {
+            game_state: address,
+            market,
+            position: pda::position(&market, &owner).0,
+            market_vault: pda::market_vault(&address).0,
+            usdc_mint: game_state.usdc_mint,
+            owner_usdc_account,
+            owner,
+            token_program,
+        },
+        instruction::ClaimPrediction {
+            game_id: game_state.game_id,
+        },
+        vec![],
+    )
+}
+
+/// Chain game `next_game_id` after `previous_game_state` on the same mint
+pub fn start_next_season(
+    previous_game_state: &GameState,
+    next_game_id: u64,
+    target_multiplier_bps: u32,
+    snail_start_stamp: i64,
+    duration: i64,
+) -> Instruction {
+    let game_id = previous_game_state.game_id;
+    let game_state = pda::game_state(next_game_id).0;
+    build(
+        accounts::StartNextSeason {
+            previous_game_state: pda::game_state(game_id).0,
+            game_state,
+            observations: pda::observations(&game_state).0,
+            snail_mint: previous_game_state.snail_mint,
+            previous_freeze_authority: pda::freeze_authority(game_id).0,
+            freeze_authority: pda::freeze_authority(next_game_id).0,
+            extra_account_meta_list: pda::extra_account_meta_list(&previous_game_state.snail_mint).0,
+            owner: previous_game_state.owner,
+            token_program: token_2022::ID,
+            system_program: system_program::ID,
+        },
+        instruction::StartNextSeason {
+            game_id,
+            next_game_id,
+            target_multiplier_bps,
+            snail_start_stamp,
+            duration,
+        },
+        vec![],
+    )
+}
+
+/// Add a secondary pool, read from its AMM pool state account `pool.pool`
+pub fn add_pool(game_state: &GameState, pool: &PoolConfig) -> Instruction {
+    build(
+        accounts::AddPool {
+            game_state: pda::game_state(game_state.game_id).0,
+            owner: game_state.owner,
+            usdc_vault: pool.usdc_vault,
+            snail_vault: pool.snail_vault,
+            pool: pool_account(pool),
+            extra_account_meta_list: pda::extra_account_meta_list(&game_state.snail_mint).0,
+        },
+        instruction::AddPool {
+            _game_id: game_state.game_id,
+            price_source: pool.price_source,
+        },
+        vec![],
+    )
+}
+
+pub fn configure_supply(
+    game_state: &GameState,
+    supply_mode: SupplyMode,
+    excluded_accounts: Vec<Pubkey>,
+) -> Instruction {
+    let remaining_accounts = readonly(&excluded_accounts);
+    build(
+        accounts::ConfigureSupply {
+            game_state: pda::game_state(game_state.game_id).0,
+            owner: game_state.owner,
+            extra_account_meta_list: pda::extra_account_meta_list(&game_state.snail_mint).0,
+        },
+        instruction::ConfigureSupply {
+            _game_id: game_state.game_id,
+            supply_mode,
+            excluded_accounts,
+        },
+        remaining_accounts,
+    )
+}
+
+pub fn configure_freeze_accounts(game_state: &GameState, freeze_accounts: Vec<Pubkey>) -> Instruction {
+    let remaining_accounts = readonly(&freeze_accounts);
+    build(
+        accounts::ConfigureFreezeAccounts {
+            game_state: pda::game_state(game_state.game_id).0,
+            owner: game_state.owner,
+        },
+        instruction::ConfigureFreezeAccounts {
+            _game_id: game_state.game_id,
+            freeze_accounts,
+        },
+        remaining_accounts,
+    )
+}
+
+/// Create the transfer hook's account list; pools and supply are fixed afterwards
+pub fn initialize_extra_account_meta_list(game_state: &GameState, payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitializeExtraAccountMetaList {
+            payer,
+            extra_account_meta_list: pda::extra_account_meta_list(&game_state.snail_mint).0,
+            game_state: pda::game_state(game_state.game_id).0,
+            owner: game_state.owner,
+            snail_mint: game_state.snail_mint,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeExtraAccountMetaList {
+            _game_id: game_state.game_id,
+        },
+        vec![],
+    )
+}
+
+pub fn record_observation(game_state: &GameState) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::RecordObservation {
+            game_state: address,
+            observations: pda::observations(&address).0,
+            usdc_lp: game_state.usdc_lp,
+            snail_lp: game_state.snail_lp,
+            snail_mint: game_state.snail_mint,
+            pool: primary_pool(game_state),
+        },
+        instruction::RecordObservation {
+            _game_id: game_state.game_id,
+        },
+        market_cap_accounts(game_state),
+    )
+}
+
+pub fn initialize_checkpoint_log(game_id: u64, payer: Pubkey) -> Instruction {
+    let address = pda::game_state(game_id).0;
+    build(
+        accounts::InitializeCheckpointLog {
+            game_state: address,
+            checkpoints: pda::checkpoints(&address).0,
+            payer,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeCheckpointLog { _game_id: game_id },
+        vec![],
+    )
+}
+
+pub fn checkpoint(game_state: &GameState) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::RecordCheckpoint {
+            game_state: address,
+            checkpoints: pda::checkpoints(&address).0,
+            observations: pda::observations(&address).0,
+            usdc_lp: game_state.usdc_lp,
+            snail_lp: game_state.snail_lp,
+            snail_mint: game_state.snail_mint,
+            pool: primary_pool(game_state),
+        },
+        instruction::Checkpoint {
+            _game_id: game_state.game_id,
+        },
+        market_cap_accounts(game_state),
+    )
+}
+
+pub fn get_checkpoints(game_id: u64, count: u16) -> Instruction {
+    let address = pda::game_state(game_id).0;
+    build(
+        accounts::GetCheckpoints {
+            game_state: address,
+            checkpoints: pda::checkpoints(&address).0,
+        },
+        instruction::GetCheckpoints {
+            _game_id: game_id,
+            count,
+        },
+        vec![],
+    )
+}
+
+pub fn get_game_status(game_state: &GameState) -> Instruction {
+    let address = pda::game_state(game_state.game_id).0;
+    build(
+        accounts::GetGameStatus {
+            game_state: address,
+            observations: pda::observations(&address).0,
+            usdc_lp: game_state.usdc_lp,
+            snail_lp: game_state.snail_lp,
+            snail_mint: game_state.snail_mint,
+            freeze_authority: pda::freeze_authority(game_state.game_id).0,
+            pool: primary_pool(game_state),
+        },
+        instruction::GetGameStatus {
+            _game_id: game_state.game_id,
+        },
+        market_cap_accounts(game_state),
+    )
+}
+
+/// Remaining accounts read by the market cap: for each secondary pool its USDC
+/// vault, its SNAIL vault and its pool state account when the source reads one,
+/// then the excluded accounts in circulating supply mode
+pub fn market_cap_accounts(game_state: &GameState) -> Vec<AccountMeta> {
+    let mut accounts = Vec::with_capacity(game_state.market_cap_account_count());
+    for config in &game_state.pools {
+        accounts.push(AccountMeta::new_readonly(config.usdc_vault, false));
+        accounts.push(AccountMeta::new_readonly(config.snail_vault, false));
+        if let Some(pool) = pool_account(config) {
+            accounts.push(AccountMeta::new_readonly(pool, false));
+        }
+    }
+    if game_state.supply_mode == SupplyMode::Circulating {
+        accounts.extend(readonly(&game_state.excluded_accounts));
+    }
+    accounts
+}
+
+/// Remaining accounts of `touch_snail` and `confirm_touch`: the market cap
+/// accounts with every secondary SNAIL vault writable, then the freeze accounts
+pub fn touch_remaining_accounts(game_state: &GameState) -> Vec<AccountMeta> {
+    let mut accounts = market_cap_accounts(game_state);
+    for account in &mut accounts {
+        account.is_writable = game_state.pools.iter().any(|pool| pool.snail_vault == account.pubkey);
+    }
+    accounts.extend(game_state.freeze_accounts.iter().map(|address| AccountMeta::new(*address, false)));
+    accounts
+}
+
+fn touch_snail_accounts(
+    game_state: &GameState,
+    toucher: Pubkey,
+    pending_toucher: Option<Pubkey>,
+    touch_accounts: TouchAccounts,
+) -> accounts::TouchSnail {
+    let address = pda::game_state(game_state.game_id).0;
+    let has_bounty = game_state.has_bounty();
+    let has_outcome_vault = game_state.outcome_mint != Pubkey::default();
+    accounts::TouchSnail {
+        game_state: address,
+        observations: pda::observations(&address).0,
+        usdc_lp: game_state.usdc_lp,
+        snail_lp: game_state.snail_lp,
+        snail_mint: game_state.snail_mint,
+        pool: primary_pool(game_state),
+        freeze_authority: pda::freeze_authority(game_state.game_id).0,
+        token_program: token_2022::ID,
+        toucher,
+        pending_toucher,
+        bounty_vault: has_bounty.then(|| pda::bounty_vault(&address).0),
+        bounty_mint: has_bounty.then_some(game_state.bounty_mint),
+        toucher_token_account: touch_accounts.toucher_token_account,
+        bounty_token_program: touch_accounts.bounty_token_program,
+        outcome_vault: has_outcome_vault.then(|| pda::outcome_vault(&address).0),
+        outcome_mint: has_outcome_vault.then_some(game_state.outcome_mint),
+        penalty_token_account: touch_accounts.penalty_token_account,
+        outcome_token_program: touch_accounts.outcome_token_program,
+    }
+}
+
+/// Pool state account of the game's primary pool, if its price source reads one
+fn primary_pool(game_state: &GameState) -> Option<Pubkey> {
+    game_state.price_source.program_id().map(|_| game_state.pool)
+}
+
+/// Pool state account of `pool`, if its price source reads one
+fn pool_account(pool: &PoolConfig) -> Option<Pubkey> {
+    pool.price_source.program_id().map(|_| pool.pool)
+}
+
+fn readonly(addresses: &[Pubkey]) -> Vec<AccountMeta> {
+    addresses.iter().map(|address| AccountMeta::new_readonly(*address, false)).collect()
+}
+
+fn build(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut accounts = accounts.to_account_metas(None);
+    accounts.extend(remaining_accounts);
+    Instruction {
+        program_id: snail_game::ID,
+        accounts,
+        data: data.data(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snail_game::PriceSource;
+
+    fn pool(price_source: PriceSource) -> PoolConfig {
+        PoolConfig {
+            price_source,
+            pool: Pubkey::new_unique(),
+            usdc_vault: Pubkey::new_unique(),
+            snail_vault: Pubkey::new_unique(),
+        }
+    }
+
+    fn game() -> GameState {
+        GameState {
+            game_id: 3,
+            usdc_lp: Pubkey::new_unique(),
+            snail_lp: Pubkey::new_unique(),
+            snail_mint: Pubkey::new_unique(),
+            pools: vec![pool(PriceSource::TokenAccounts), pool(PriceSource::RaydiumCpmm)],
+            supply_mode: SupplyMode::Circulating,
+            excluded_accounts: vec![Pubkey::new_unique()],
+            freeze_accounts: vec![Pubkey::new_unique()],
+            ..Default::default()
+        }
+    }
+
+    fn keys(accounts: &[AccountMeta]) -> Vec<(Pubkey, bool)> {
+        accounts.iter().map(|account| (account.pubkey, account.is_writable)).collect()
+    }
+
+    #[test]
+    fn market_cap_accounts_follow_spot_market_cap() {
+        let game_state = game();
+        let (cpmm, token_accounts) = (&game_state.pools[1], &game_state.pools[0]);
+        let accounts = market_cap_accounts(&game_state);
+        assert_eq!(accounts.len(), game_state.market_cap_account_count());
+        assert_eq!(
+            keys(&accounts),
+            vec![
+                (token_accounts.usdc_vault, false),
+                (token_accounts.snail_vault, false),
+                (cpmm.usdc_vault, false),
+                (cpmm.snail_vault, false),
+                (cpmm.pool, false),
+                (game_state.excluded_accounts[0], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn touch_writes_snail_vaults_and_freeze_accounts() {
+        let game_state = game();
+        let accounts = touch_remaining_accounts(&game_state);
+        let writable: Vec<_> = accounts
+            .iter()
+            .filter(|account| account.is_writable)
+            .map(|account| account.pubkey)
+            .collect();
+        assert_eq!(
+            writable,
+            vec![
+                game_state.pools[0].snail_vault,
+                game_state.pools[1].snail_vault,
+                game_state.freeze_accounts[0],
+            ]
+        );
+        assert_eq!(accounts.last().unwrap().pubkey, game_state.freeze_accounts[0]);
+    }
+
+    #[test]
+    fn touch_fills_vaults_from_game_state() {
+        let address = pda::game_state(3).0;
+        let without = touch_snail_accounts(&game(), Pubkey::new_unique(), None, TouchAccounts::default());
+        assert_eq!(without.bounty_vault, None);
+        assert_eq!(without.outcome_vault, None);
+        assert_eq!(without.pool, None);
+
+        let game_state = GameState {
+            bounty_mint: Pubkey::new_unique(),
+            outcome_mint: Pubkey::new_unique(),
+            price_source: PriceSource::OrcaWhirlpool,
+            pool: Pubkey::new_unique(),
+            ..game()
+        };
+        let with = touch_snail_accounts(&game_state, Pubkey::new_unique(), None, TouchAccounts::default());
+        assert_eq!(with.bounty_vault, Some(pda::bounty_vault(&address).0));
+        assert_eq!(with.bounty_mint, Some(game_state.bounty_mint));
+        assert_eq!(with.outcome_vault, Some(pda::outcome_vault(&address).0));
+        assert_eq!(with.outcome_mint, Some(game_state.outcome_mint));
+        assert_eq!(with.pool, Some(game_state.pool));
+    }
+
+    #[test]
+    fn absent_optional_accounts_are_the_program_id() {
+        let instruction = touch_snail(&game(), Pubkey::new_unique(), TouchAccounts::default());
+        let pool = &instruction.accounts[5];
+        assert_eq!(pool.pubkey, snail_game::ID);
+        assert!(!pool.is_writable);
+    }
+
+    #[test]
+    fn confirm_touch_passes_the_pending_toucher() {
+        let game_state = GameState {
+            toucher: Pubkey::new_unique(),
+            ..game()
+        };
+        let confirm = confirm_touch(&game_state, Pubkey::new_unique(), TouchAccounts::default());
+        assert_eq!(confirm.accounts[9].pubkey, game_state.toucher);
+        let touch = touch_snail(&game_state, Pubkey::new_unique(), TouchAccounts::default());
+        assert_eq!(touch.accounts[9].pubkey, snail_game::ID);
+    }
+}
@@ -0,0 +1,155 @@
+//! Instruction builders for `snail_launch`
+//!
+//! The launch is a singleton: its PDAs do not depend on any id, and the treasury
+//! token account is the treasury PDA's Token-2022 associated token account.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::token_2022;
+use snail_launch::{accounts, instruction};
+
+use crate::pda;
+
+/// Treasury token account holding the unclaimed supply
+pub fn treasury_token_account(snail_mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(&pda::treasury().0, snail_mint, &token_2022::ID)
+}
+
+/// Mint the full supply to the treasury and revoke the mint authority
+/// The mint authority of `snail_mint` must be `pda::mint_authority()`.
+pub fn initialize(owner: Pubkey, snail_mint: Pubkey) -> Instruction {
+    build(
+        accounts::Initialize {
+            launch_state: pda::launch_state().0,
+            owner,
+            snail_mint,
+            treasury_pda: pda::treasury().0,
+            treasury_token_account: treasury_token_account(&snail_mint),
+            mint_authority: pda::mint_authority().0,
+            token_program: token_2022::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::Initialize {},
+    )
+}
+
+pub fn claim_admin_lp(owner: Pubkey, snail_mint: Pubkey, admin_token_account: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimAdminLp {
+            launch_state: pda::launch_state().0,
+            owner,
+            snail_mint,
+            admin_token_account,
+            treasury_pda: pda::treasury().0,
+            treasury_token_account: treasury_token_account(&snail_mint),
+            token_program: token_2022::ID,
+        },
+        instruction::ClaimAdminLp {},
+    )
+}
+
+pub fn initialize_sale(owner: Pubkey, start_time: i64, end_time: i64, claim_stamp: i64) -> Instruction {
+    build(
+        accounts::InitializeSale {
+            launch_state: pda::launch_state().0,
+            owner,
+        },
+        instruction::InitializeSale {
+            start_time,
+            end_time,
+            claim_stamp,
+        },
+    )
+}
+
+/// Contribute `amount` lamports to the public sale
+pub fn contribute(contributor: Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::Contribute {
+            launch_state: pda::launch_state().0,
+            contributor,
+            contributor_data: pda::contributor(&contributor).0,
+            sale_vault: pda::sale_vault().0,
+            system_program: system_program::ID,
+        },
+        instruction::Contribute { amount },
+    )
+}
+
+pub fn claim_snail(contributor: Pubkey, snail_mint: Pubkey, contributor_token_account: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimSnail {
+            launch_state: pda::launch_state().0,
+            contributor,
+            contributor_data: pda::contributor(&contributor).0,
+            snail_mint,
+            contributor_token_account,
+            treasury_pda: pda::treasury().0,
+            treasury_token_account: treasury_token_account(&snail_mint),
+            token_program: token_2022::ID,
+        },
+        instruction::ClaimSnail {},
+    )
+}
+
+/// View: SNAIL `contributor` can claim, as a `u64` in the return data
+pub fn snail_available(contributor: Pubkey, snail_mint: Pubkey) -> Instruction {
+    build(
+        accounts::SnailAvailable {
+            launch_state: pda::launch_state().0,
+            contributor_data: pda::contributor(&contributor).0,
+            contributor,
+            snail_mint,
+            token_program: token_2022::ID,
+        },
+        instruction::SnailAvailable {},
+    )
+}
+
+pub fn claim_admin_sol(owner: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimAdminSol {
+            launch_state: pda::launch_state().0,
+            sale_vault: pda::sale_vault().0,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimAdminSol {},
+    )
+}
+
+pub fn airdrop(owner: Pubkey, snail_mint: Pubkey, recipient_token_account: Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::Airdrop {
+            launch_state: pda::launch_state().0,
+            owner,
+            snail_mint,
+            recipient_token_account,
+            treasury_pda: pda::treasury().0,
+            treasury_token_account: treasury_token_account(&snail_mint),
+            token_program: token_2022::ID,
+        },
+        instruction::Airdrop { amount },
+    )
+}
+
+pub fn revoke_ownership(owner: Pubkey) -> Instruction {
+    build(
+        accounts::RevokeOwnership {
+            launch_state: pda::launch_state().0,
+            owner,
+        },
+        instruction::RevokeOwnership {},
+    )
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: snail_launch::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
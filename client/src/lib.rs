@@ -0,0 +1,15 @@
+//! Rust client for the snail programs
+//!
+//! Typed instruction builders (`game`, `launch`), PDA derivation (`pda`),
+//! account and return data decoders (`accounts`) and the market cap math of
+//! `snail_game` evaluated off-chain (`math`). Builders return plain
+//! `Instruction`s; signing and sending them is left to the caller's RPC client.
+
+pub mod accounts;
+pub mod game;
+pub mod launch;
+pub mod math;
+pub mod pda;
+
+pub use snail_game;
+pub use snail_launch;
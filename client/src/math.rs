@@ -0,0 +1,244 @@
+//! Market cap math of `snail_game`, evaluated off-chain
+//!
+//! Takes the token balances and pool accounts the caller has already fetched
+//! and returns what the program's views would: the required market cap, the
+//! spot and reported market caps and the game status. Every one of them calls
+//! the program's own pure functions (curve, `pools_market_cap`, `curve_samples`,
+//! `reported_market_cap`, `game_status`), so results match on-chain to the unit
+//! as long as the inputs come from the same slot.
+
+use anchor_lang::prelude::*;
+pub use snail_game::price::PoolSnapshot;
+use snail_game::{curve, CurvePoint, GameState, GameStatus, ObservationBuffer, SnailError, SupplyMode};
+
+/// Required market cap at `time_point`, as returned by `check_required_market_cap`
+pub fn required_market_cap(game_state: &GameState, time_point: i64) -> Result<u64> {
+    Ok(curve::required_market_cap(game_state, time_point)? as u64)
+}
+
+/// Required market cap sampled at the time points `preview_game` uses
+pub fn curve_samples(game_state: &GameState) -> Result<Vec<CurvePoint>> {
+    snail_game::curve_samples(game_state)
+}
+
+/// Supply the market cap is computed from: the mint supply, less the balances of
+/// `game_state.excluded_accounts` (in that order) in circulating supply mode
+pub fn supply(game_state: &GameState, mint_supply: u64, excluded_balances: &[u64]) -> Result<u64> {
+    match game_state.supply_mode {
+        SupplyMode::FullyDiluted => Ok(mint_supply),
+        SupplyMode::Circulating => {
+            require!(
+                excluded_balances.len() == game_state.excluded_accounts.len(),
+                SnailError::MissingExcludedAccount
+            );
+            Ok(excluded_balances.iter().fold(mint_supply, |supply, balance| supply.saturating_sub(*balance)))
+        }
+    }
+}
+
+/// Spot market cap of `supply`, weighted by the USDC each pool holds
+/// `pools` holds the primary pool followed by `game_state.pools` in order.
+/// Returns None when no pool has liquidity.
+pub fn spot_market_cap(game_state: &GameState, pools: &[PoolSnapshot], supply: u64) -> Result<Option<u128>> {
+    snail_game::pools_market_cap(game_state, pools, supply)
+}
+
+/// Market cap reported by `get_game_status`: the TWAP over the game's window when
+/// it has one, falling back to `spot_market_cap` while the window fills up
+/// `touch_snail` records an observation first, so its value can differ by the
+/// sample it adds.
+pub fn current_market_cap(
+    game_state: &GameState,
+    observations: &ObservationBuffer,
+    spot_market_cap: u128,
+    timestamp: i64,
+) -> u128 {
+    snail_game::reported_market_cap(game_state, observations, spot_market_cap, timestamp)
+}
+
+/// Status as returned by `get_game_status` at `time_point`
+/// `time_point` and `time_remaining` are in the game's timing mode: seconds for
+/// `UnixTimestamp`, slots for `Slot`. `armed` is whether the mint's freeze authority is still the game's freeze
+/// authority PDA, see `is_armed`.
+pub fn game_status(
+    game_state: &GameState,
+    current_market_cap: u128,
+    time_point: i64,
+    armed: bool,
+) -> Result<GameStatus> {
+    require!(game_state.is_configured(), SnailError::NotConfigured);
+    snail_game::game_status(game_state, current_market_cap, time_point, armed)
+}
+
+/// Whether a mint with `freeze_authority` can still be frozen by game `game_id`
+pub fn is_armed(game_id: u64, freeze_authority: Option<Pubkey>) -> bool {
+    freeze_authority == Some(crate::pda::freeze_authority(game_id).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snail_game::{CurveKind, GamePhase, Observation, PoolConfig, PriceSource, MAX_OBSERVATIONS, PREVIEW_SAMPLES};
+    use snail_game::price::Vault;
+
+    const SNAIL_MINT: Pubkey = Pubkey::new_from_array([1; 32]);
+    const USDC_MINT: Pubkey = Pubkey::new_from_array([2; 32]);
+    const START: i64 = 1_700_000_000;
+    const DURATION: i64 = 30 * 24 * 60 * 60;
+    const TARGET: u64 = 1_000_000; // 1M USDC with market_cap_decimals = 0
+    const SUPPLY: u64 = 1_000_000_000_000_000; // 1M SNAIL with 9 decimals
+
+    fn game() -> GameState {
+        GameState {
+            snail_start_stamp: START,
+            snail_end_stamp: START + DURATION,
+            target_market_cap: TARGET,
+            curve: CurveKind::Linear,
+            snail_mint: SNAIL_MINT,
+            usdc_mint: USDC_MINT,
+            snail_decimals: 9,
+            usdc_decimals: 6,
+            market_cap_decimals: 0,
+            phase: GamePhase::Live,
+            ..Default::default()
+        }
+    }
+
+    /// Constant-product pool holding `usdc` whole USDC against `snail` whole SNAIL
+    fn pool(usdc: u64, snail: u64) -> PoolSnapshot<'static> {
+        PoolSnapshot {
+            usdc_vault: Vault { address: Pubkey::new_unique(), mint: USDC_MINT, amount: usdc * 1_000_000 },
+            snail_vault: Vault { address: Pubkey::new_unique(), mint: SNAIL_MINT, amount: snail * 1_000_000_000 },
+            pool: None,
+        }
+    }
+
+    fn with_secondary_pool(game_state: GameState) -> GameState {
+        GameState {
+            pools: vec![PoolConfig::default()],
+            ..game_state
+        }
+    }
+
+    #[test]
+    fn required_market_cap_follows_the_curve() {
+        let game_state = game();
+        assert_eq!(required_market_cap(&game_state, START + DURATION / 2).unwrap(), TARGET / 2);
+        assert_eq!(required_market_cap(&game_state, START + DURATION).unwrap(), 0);
+    }
+
+    #[test]
+    fn curve_samples_span_the_window() {
+        let game_state = game();
+        let samples = curve_samples(&game_state).unwrap();
+        assert_eq!(samples.len(), PREVIEW_SAMPLES);
+        assert_eq!(samples[0].time_point, START);
+        assert_eq!(samples[PREVIEW_SAMPLES - 1].time_point, START + DURATION - 1);
+        for sample in samples {
+            assert_eq!(
+                sample.required_market_cap,
+                required_market_cap(&game_state, sample.time_point).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn spot_market_cap_from_one_pool() {
+        // 1 USDC per SNAIL
+        let market_cap = spot_market_cap(&game(), &[pool(100_000, 100_000)], SUPPLY).unwrap();
+        assert_eq!(market_cap, Some(1_000_000));
+    }
+
+    #[test]
+    fn spot_market_cap_weights_pools_by_usdc() {
+        // 1 USDC per SNAIL with 300k USDC, 5 USDC per SNAIL with 100k USDC
+        let game_state = with_secondary_pool(game());
+        let pools = [pool(300_000, 300_000), pool(100_000, 20_000)];
+        assert_eq!(spot_market_cap(&game_state, &pools, SUPPLY).unwrap(), Some(2_000_000));
+    }
+
+    #[test]
+    fn spot_market_cap_skips_empty_pools() {
+        let game_state = with_secondary_pool(game());
+        let pools = [pool(100_000, 100_000), pool(0, 0)];
+        assert_eq!(spot_market_cap(&game_state, &pools, SUPPLY).unwrap(), Some(1_000_000));
+        assert_eq!(spot_market_cap(&game_state, &[pool(0, 0), pool(0, 0)], SUPPLY).unwrap(), None);
+    }
+
+    #[test]
+    fn spot_market_cap_needs_every_pool() {
+        let game_state = with_secondary_pool(game());
+        assert!(spot_market_cap(&game_state, &[pool(100_000, 100_000)], SUPPLY).is_err());
+    }
+
+    #[test]
+    fn pool_state_sources_need_the_pool_account() {
+        let game_state = GameState {
+            price_source: PriceSource::OrcaWhirlpool,
+            ..game()
+        };
+        assert!(spot_market_cap(&game_state, &[pool(100_000, 100_000)], SUPPLY).is_err());
+    }
+
+    #[test]
+    fn circulating_supply_subtracts_excluded_balances() {
+        let game_state = GameState {
+            supply_mode: SupplyMode::Circulating,
+            excluded_accounts: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            ..game()
+        };
+        assert_eq!(supply(&game_state, SUPPLY, &[SUPPLY / 4, SUPPLY / 4]).unwrap(), SUPPLY / 2);
+        assert_eq!(supply(&game_state, SUPPLY, &[SUPPLY, 1]).unwrap(), 0);
+        assert!(supply(&game_state, SUPPLY, &[SUPPLY / 4]).is_err());
+        assert_eq!(supply(&game(), SUPPLY, &[]).unwrap(), SUPPLY);
+    }
+
+    #[test]
+    fn current_market_cap_uses_the_twap_once_the_window_is_filled() {
+        let game_state = GameState { twap_window: 150, ..game() };
+        let mut observations = ObservationBuffer {
+            game_state: Pubkey::default(),
+            head: 0,
+            count: 0,
+            observations: [Observation::default(); MAX_OBSERVATIONS],
+        };
+        observations.record(START, 100, 1).unwrap();
+        observations.record(START + 100, 300, 1).unwrap();
+
+        // (100 * 100s + 300 * 100s) / 200s
+        assert_eq!(current_market_cap(&game_state, &observations, 500, START + 200), 200);
+        // Window reaches back before the first observation
+        assert_eq!(current_market_cap(&game_state, &observations, 500, START + 100), 500);
+        assert_eq!(current_market_cap(&game(), &observations, 500, START + 200), 500);
+    }
+
+    #[test]
+    fn game_status_reports_the_margin() {
+        let game_state = game();
+        let status = game_status(&game_state, 400_000, START + DURATION / 2, true).unwrap();
+        assert_eq!(status.phase, GamePhase::Live);
+        assert_eq!(status.required_market_cap, TARGET / 2);
+        assert_eq!(status.margin, -100_000);
+        assert_eq!(status.time_remaining, DURATION / 2);
+
+        let survived = GameState { phase: GamePhase::Survived, ..game() };
+        assert_eq!(game_status(&survived, 0, START, true).unwrap().time_remaining, 0);
+
+        let unconfigured = GameState { phase: GamePhase::Uninitialized, ..game() };
+        assert!(game_status(&unconfigured, 0, START, true).is_err());
+    }
+
+    #[test]
+    fn pending_games_turn_live_at_the_start() {
+        let game_state = GameState { phase: GamePhase::Pending, ..game() };
+        assert_eq!(game_status(&game_state, 0, START - 1, true).unwrap().phase, GamePhase::Pending);
+        assert_eq!(game_status(&game_state, 0, START, true).unwrap().phase, GamePhase::Live);
+    }
+
+    #[test]
+    fn armed_only_by_the_game_freeze_authority() {
+        assert!(is_armed(7, Some(crate::pda::freeze_authority(7).0)));
+        assert!(!is_armed(7, Some(crate::pda::freeze_authority(8).0)));
+        assert!(!is_armed(7, None));
+    }
+}
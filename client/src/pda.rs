@@ -0,0 +1,109 @@
+//! Program derived addresses of both programs
+//!
+//! Every function returns the address with its bump, like
+//! `Pubkey::find_program_address`.
+
+use anchor_lang::prelude::Pubkey;
+
+// ============================================================================
+// SNAIL GAME
+// ============================================================================
+
+/// `["game_state", game_id]`, `game_id` as little-endian u64
+pub fn game_state(game_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game_state", &game_id.to_le_bytes()], &snail_game::ID)
+}
+
+/// `["freeze-authority", game_id]`, the freeze authority the game's mint must have
+pub fn freeze_authority(game_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"freeze-authority", &game_id.to_le_bytes()], &snail_game::ID)
+}
+
+/// `["observations", game_state]`, the TWAP ring buffer
+pub fn observations(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"observations", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["checkpoints", game_state]`
+pub fn checkpoints(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"checkpoints", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["bounty_vault", game_state]`
+pub fn bounty_vault(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bounty_vault", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["outcome_vault", game_state]`
+pub fn outcome_vault(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"outcome_vault", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["shell", game_state]`
+pub fn shell(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"shell", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["shell_reward_vault", game_state]`
+pub fn shell_reward_vault(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"shell_reward_vault", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["stake", game_state, owner]`
+pub fn stake(game_state: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stake", game_state.as_ref(), owner.as_ref()], &snail_game::ID)
+}
+
+/// `["stake_vault", stake]`
+pub fn stake_vault(stake: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stake_vault", stake.as_ref()], &snail_game::ID)
+}
+
+/// `["market", game_state]`
+pub fn market(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"market", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["market_vault", game_state]`
+pub fn market_vault(game_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"market_vault", game_state.as_ref()], &snail_game::ID)
+}
+
+/// `["position", market, owner]`
+pub fn position(market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"position", market.as_ref(), owner.as_ref()], &snail_game::ID)
+}
+
+/// `["extra-account-metas", snail_mint]`, read by Token-2022 to call the transfer hook
+pub fn extra_account_meta_list(snail_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"extra-account-metas", snail_mint.as_ref()], &snail_game::ID)
+}
+
+// ============================================================================
+// SNAIL LAUNCH
+// ============================================================================
+
+/// `["launch_state"]`
+pub fn launch_state() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"launch_state"], &snail_launch::ID)
+}
+
+/// `["treasury"]`, authority of the treasury token account
+pub fn treasury() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury"], &snail_launch::ID)
+}
+
+/// `["mint_authority"]`, revoked by `initialize`
+pub fn mint_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"mint_authority"], &snail_launch::ID)
+}
+
+/// `["sale_vault"]`, holds the SOL raised by the public sale
+pub fn sale_vault() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"sale_vault"], &snail_launch::ID)
+}
+
+/// `["contributor", contributor]`
+pub fn contributor(contributor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"contributor", contributor.as_ref()], &snail_launch::ID)
+}
@@ -0,0 +1,408 @@
+//! Required market cap curve
+//!
+//! Pure functions over `GameState`, shared by the on-chain instructions and
+//! usable off-chain (clients, simulations) through the `lib` target.
+
+use anchor_lang::prelude::*;
+
+use crate::math::{self, SCALE};
+use crate::{GameState, SnailError};
+
+/// Required market cap at `time_point`, in the game's timing mode (unix timestamp or slot)
+/// Returns 0 for unconfigured games, before `snail_start_stamp` and from
+/// `snail_end_stamp` onwards; otherwise `target_market_cap * curve(progress)`.
+pub fn required_market_cap(game_state: &GameState, time_point: i64) -> Result<u128> {
+    if !game_state.is_configured()
+        || time_point < game_state.snail_start_stamp
+        || time_point >= game_state.snail_end_stamp
+    {
+        return Ok(0);
+    }
+
+    // Calculate progress (0 to 1, scaled by 1e18 for precision)
+    let elapsed = (time_point - game_state.snail_start_stamp) as u64;
+    let duration = (game_state.snail_end_stamp - game_state.snail_start_stamp) as u64;
+    let progress = ((elapsed as u128) * SCALE) / (duration as u128);
+
+    let curved_progress = game_state.curve.evaluate(progress)?;
+
+    // Calculate required market cap
+    Ok(((game_state.target_market_cap as u128) * curved_progress) / SCALE)
+}
+
+/// How the curve's fractional exponent is evaluated
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExponentMode {
+    /// Original behaviour: linear interpolation between integer powers
+    Interpolated,
+    /// Fixed-point 2^(exponent * log2(progress)), see `math`
+    Exact,
+}
+
+impl ExponentMode {
+    pub fn pow(self, base: u128, exponent: u128) -> Result<u128> {
+        match self {
+            ExponentMode::Interpolated => math::pow_interpolated(base, exponent),
+            ExponentMode::Exact => math::pow(base, exponent),
+        }
+    }
+}
+
+/// Maximum number of interior knots of a piecewise-linear curve
+pub const MAX_CURVE_KNOTS: usize = 8;
+
+/// Maximum exponential steepness, stored with 1 decimal precision (200 = 20.0)
+pub const MAX_STEEPNESS: u64 = 200;
+
+/// Maximum number of steps of a step-wise curve
+pub const MAX_STEPS: u16 = 1_000;
+
+/// Basis points representing the whole game / the full target
+pub const BPS: u16 = 10_000;
+
+/// Interior point of a piecewise-linear curve, both coordinates in basis points
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CurveKnot {
+    pub progress: u16,
+    pub value: u16,
+}
+
+/// Shape of the required market cap over the game
+/// Every kind maps progress in [0, 1) to a fraction of the target in [0, 1],
+/// starting at 0 and rising towards 1 at the end stamp.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug, Default)]
+pub enum CurveKind {
+    /// progress
+    #[default]
+    Linear,
+    /// progress^(1 + curve_factor * 0.04)
+    Power {
+        curve_factor: u64, // Stored with 1 decimal precision (77 = 7.7), 0..=100
+        exponent_mode: ExponentMode,
+    },
+    /// (e^(k * progress) - 1) / (e^k - 1)
+    Exponential {
+        steepness: u64, // k, stored with 1 decimal precision, 1..=MAX_STEEPNESS
+    },
+    /// floor(progress * steps) / steps
+    Step { steps: u16 },
+    /// Straight segments from (0, 0) through the knots to (BPS, BPS)
+    PiecewiseLinear { knots: Vec<CurveKnot> },
+}
+
+impl CurveKind {
+    pub const LEN: usize = 1 + // variant
+        4 + MAX_CURVE_KNOTS * (2 + 2); // largest variant: knots
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            CurveKind::Linear => {}
+            CurveKind::Power { curve_factor, .. } => {
+                require!(*curve_factor <= 100, SnailError::InvalidCurveFactor);
+            }
+            CurveKind::Exponential { steepness } => {
+                require!(
+                    *steepness > 0 && *steepness <= MAX_STEEPNESS,
+                    SnailError::InvalidCurveSteepness
+                );
+            }
+            CurveKind::Step { steps } => {
+                require!(*steps >= 2 && *steps <= MAX_STEPS, SnailError::InvalidCurveSteps);
+            }
+            CurveKind::PiecewiseLinear { knots } => {
+                require!(
+                    !knots.is_empty() && knots.len() <= MAX_CURVE_KNOTS,
+                    SnailError::InvalidCurveKnots
+                );
+                let mut previous = CurveKnot { progress: 0, value: 0 };
+                for knot in knots {
+                    require!(
+                        knot.progress > previous.progress
+                            && knot.progress < BPS
+                            && knot.value >= previous.value
+                            && knot.value <= BPS,
+                        SnailError::InvalidCurveKnots
+                    );
+                    previous = *knot;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fraction of the target required at `progress` (both scaled by 1e18)
+    pub fn evaluate(&self, progress: u128) -> Result<u128> {
+        match self {
+            CurveKind::Linear => Ok(progress),
+            CurveKind::Power { curve_factor, exponent_mode } => {
+                // Exponent = 1 + (curveFactor / 10) * 0.4 = 1 + curveFactor * 0.04
+                // Scaled: exponent = 1e18 + curveFactor * 4e16
+                let exponent = SCALE + (*curve_factor as u128) * 40_000_000_000_000_000u128;
+                exponent_mode.pow(progress, exponent)
+            }
+            CurveKind::Exponential { steepness } => {
+                // Rewritten as e^(k(p - 1)) * (1 - e^(-kp)) / (1 - e^(-k)) so every
+                // factor stays at or below 1.0
+                let scale = SCALE as i128;
+                let k = (*steepness as i128) * scale / 10;
+                let p = progress as i128;
+                let head = math::exp(k * (p - scale) / scale)?;
+                let numerator = SCALE - math::exp(-k * p / scale)?;
+                let denominator = SCALE - math::exp(-k)?;
+                Ok(head * numerator / denominator)
+            }
+            CurveKind::Step { steps } => {
+                let steps = *steps as u128;
+                Ok((progress * steps / SCALE) * SCALE / steps)
+            }
+            CurveKind::PiecewiseLinear { knots } => {
+                let point = |knot: &CurveKnot| {
+                    (
+                        knot.progress as u128 * SCALE / BPS as u128,
+                        knot.value as u128 * SCALE / BPS as u128,
+                    )
+                };
+                let mut start = (0u128, 0u128);
+                for end in knots.iter().map(point).chain([(SCALE, SCALE)]) {
+                    if progress < end.0 {
+                        return Ok(start.1 + (end.1 - start.1) * (progress - start.0) / (end.0 - start.0));
+                    }
+                    start = end;
+                }
+                Ok(SCALE)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GamePhase, TimingMode};
+    use proptest::prelude::*;
+
+    const START: i64 = 1_700_000_000;
+    const DURATION: i64 = 30 * 24 * 60 * 60;
+    const TARGET: u64 = 1_000_000_000_000; // 1M USDC in base units
+
+    fn game(curve: CurveKind) -> GameState {
+        game_with(curve, TARGET, DURATION)
+    }
+
+    fn game_with(curve: CurveKind, target_market_cap: u64, duration: i64) -> GameState {
+        GameState {
+            snail_start_stamp: START,
+            snail_end_stamp: START + duration,
+            target_market_cap,
+            curve,
+            phase: GamePhase::Live,
+            ..Default::default()
+        }
+    }
+
+    /// Required market cap at `bps` of the way through the default game
+    fn required_at_bps(game_state: &GameState, bps: i64) -> u128 {
+        required_market_cap(game_state, START + DURATION * bps / BPS as i64).unwrap()
+    }
+
+    #[test]
+    fn zero_outside_the_window() {
+        let game_state = game(CurveKind::Linear);
+        assert_eq!(required_market_cap(&game_state, START - 1).unwrap(), 0);
+        assert_eq!(required_market_cap(&game_state, START).unwrap(), 0);
+        assert_eq!(required_market_cap(&game_state, START + DURATION).unwrap(), 0);
+        assert_eq!(required_market_cap(&game_state, i64::MAX).unwrap(), 0);
+    }
+
+    #[test]
+    fn zero_when_not_configured() {
+        let game_state = GameState {
+            phase: GamePhase::Uninitialized,
+            ..game(CurveKind::Linear)
+        };
+        assert_eq!(required_at_bps(&game_state, 5_000), 0);
+    }
+
+    #[test]
+    fn linear_is_proportional_to_progress() {
+        let game_state = game(CurveKind::Linear);
+        assert_eq!(required_at_bps(&game_state, 2_500), TARGET as u128 / 4);
+        assert_eq!(required_at_bps(&game_state, 5_000), TARGET as u128 / 2);
+    }
+
+    #[test]
+    fn interpolated_power_matches_original_curve() {
+        // 0.5^4 - (0.5^4 - 0.5^5) * 0.08 = 0.06
+        let game_state = game(CurveKind::Power {
+            curve_factor: 77,
+            exponent_mode: ExponentMode::Interpolated,
+        });
+        assert_eq!(required_at_bps(&game_state, 5_000), TARGET as u128 * 6 / 100);
+    }
+
+    #[test]
+    fn exact_power_matches_documented_curve() {
+        // 0.5^4.08 = 0.0591286029203...
+        let game_state = game(CurveKind::Power {
+            curve_factor: 77,
+            exponent_mode: ExponentMode::Exact,
+        });
+        assert_eq!(required_at_bps(&game_state, 5_000), 59_128_602_920);
+    }
+
+    #[test]
+    fn exponential_matches_closed_form() {
+        // (e^2.5 - 1) / (e^5 - 1) = 0.0758581800212...
+        let game_state = game(CurveKind::Exponential { steepness: 50 });
+        assert_eq!(required_at_bps(&game_state, 5_000), 75_858_180_021);
+    }
+
+    #[test]
+    fn step_holds_between_steps() {
+        let game_state = game(CurveKind::Step { steps: 4 });
+        assert_eq!(required_at_bps(&game_state, 2_499), 0);
+        assert_eq!(required_at_bps(&game_state, 2_500), TARGET as u128 / 4);
+        assert_eq!(required_at_bps(&game_state, 4_999), TARGET as u128 / 4);
+        assert_eq!(required_at_bps(&game_state, 9_999), TARGET as u128 * 3 / 4);
+    }
+
+    #[test]
+    fn piecewise_linear_interpolates_between_knots() {
+        let game_state = game(CurveKind::PiecewiseLinear {
+            knots: vec![
+                CurveKnot { progress: 5_000, value: 1_000 },
+                CurveKnot { progress: 8_000, value: 1_000 },
+            ],
+        });
+        assert_eq!(required_at_bps(&game_state, 2_500), TARGET as u128 / 20);
+        assert_eq!(required_at_bps(&game_state, 5_000), TARGET as u128 / 10);
+        assert_eq!(required_at_bps(&game_state, 7_000), TARGET as u128 / 10);
+        assert_eq!(required_at_bps(&game_state, 9_000), TARGET as u128 * 55 / 100);
+    }
+
+    #[test]
+    fn slot_mode_follows_the_slot() {
+        // A window of 1_000 slots, read while the unix clock is far past it
+        let game_state = GameState {
+            timing_mode: TimingMode::Slot,
+            ..game_with(CurveKind::Linear, TARGET, 1_000)
+        };
+        let clock = Clock {
+            slot: START as u64 + 250,
+            unix_timestamp: START + DURATION,
+            ..Default::default()
+        };
+
+        let time_point = game_state.time_point(&clock);
+        assert_eq!(time_point, START + 250);
+        assert_eq!(game_state.phase_at(time_point), GamePhase::Live);
+        assert_eq!(required_market_cap(&game_state, time_point).unwrap(), TARGET as u128 / 4);
+
+        let game_state = GameState {
+            timing_mode: TimingMode::UnixTimestamp,
+            ..game_state
+        };
+        assert_eq!(game_state.time_point(&clock), START + DURATION);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_curves() {
+        let knot = |progress, value| CurveKnot { progress, value };
+        let invalid = [
+            CurveKind::Power {
+                curve_factor: 101,
+                exponent_mode: ExponentMode::Exact,
+            },
+            CurveKind::Exponential { steepness: 0 },
+            CurveKind::Exponential { steepness: MAX_STEEPNESS + 1 },
+            CurveKind::Step { steps: 1 },
+            CurveKind::Step { steps: MAX_STEPS + 1 },
+            CurveKind::PiecewiseLinear { knots: vec![] },
+            CurveKind::PiecewiseLinear { knots: vec![knot(0, 0)] },
+            CurveKind::PiecewiseLinear { knots: vec![knot(BPS, BPS)] },
+            CurveKind::PiecewiseLinear { knots: vec![knot(5_000, BPS + 1)] },
+            CurveKind::PiecewiseLinear { knots: vec![knot(5_000, 100), knot(5_000, 200)] },
+            CurveKind::PiecewiseLinear { knots: vec![knot(4_000, 200), knot(5_000, 100)] },
+            CurveKind::PiecewiseLinear {
+                knots: (1..=MAX_CURVE_KNOTS as u16 + 1).map(|i| knot(i * 100, i * 100)).collect(),
+            },
+        ];
+        for curve in invalid {
+            assert!(curve.validate().is_err(), "{curve:?} should be rejected");
+        }
+    }
+
+    fn curve_strategy() -> impl Strategy<Value = CurveKind> {
+        let exponent_mode = prop_oneof![Just(ExponentMode::Interpolated), Just(ExponentMode::Exact)];
+        let knots = prop::collection::vec((1..BPS, 0..=BPS), 1..=MAX_CURVE_KNOTS).prop_map(|points| {
+            let mut progress: Vec<u16> = points.iter().map(|point| point.0).collect();
+            let mut values: Vec<u16> = points.iter().map(|point| point.1).collect();
+            progress.sort_unstable();
+            progress.dedup();
+            values.sort_unstable();
+            progress
+                .into_iter()
+                .zip(values)
+                .map(|(progress, value)| CurveKnot { progress, value })
+                .collect()
+        });
+        prop_oneof![
+            Just(CurveKind::Linear),
+            (0..=100u64, exponent_mode).prop_map(|(curve_factor, exponent_mode)| CurveKind::Power {
+                curve_factor,
+                exponent_mode,
+            }),
+            (1..=MAX_STEEPNESS).prop_map(|steepness| CurveKind::Exponential { steepness }),
+            (2..=MAX_STEPS).prop_map(|steps| CurveKind::Step { steps }),
+            knots.prop_map(|knots| CurveKind::PiecewiseLinear { knots }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn generated_curves_are_valid(curve in curve_strategy()) {
+            prop_assert!(curve.validate().is_ok());
+        }
+
+        #[test]
+        fn monotonic_over_the_window(
+            curve in curve_strategy(),
+            target in any::<u64>(),
+            duration in 1..=10 * 365 * 24 * 60 * 60i64,
+            a in 0.0..1.0f64,
+            b in 0.0..1.0f64,
+        ) {
+            let game_state = game_with(curve, target, duration);
+            let (early, late) = if a <= b { (a, b) } else { (b, a) };
+            let early = START + (early * duration as f64) as i64;
+            let late = START + (late * duration as f64) as i64;
+            prop_assert!(
+                required_market_cap(&game_state, early)? <= required_market_cap(&game_state, late)?
+            );
+        }
+
+        #[test]
+        fn endpoints(
+            curve in curve_strategy(),
+            target in any::<u64>(),
+            duration in 1_000_000..=100_000_000i64,
+        ) {
+            let game_state = game_with(curve.clone(), target, duration);
+            let end = START + duration;
+
+            prop_assert_eq!(required_market_cap(&game_state, START)?, 0);
+            prop_assert_eq!(required_market_cap(&game_state, end)?, 0);
+
+            // One second before the end the curve is within its last step of the target,
+            // less the 1e-18 truncation of the curved progress
+            let last = required_market_cap(&game_state, end - 1)?;
+            let floor = match curve {
+                CurveKind::Step { steps } => target as u128 * (steps as u128 - 1) / steps as u128,
+                _ => target as u128 * 98 / 100,
+            }
+            .saturating_sub(target as u128 / SCALE + 1);
+            prop_assert!(last <= target as u128);
+            prop_assert!(last >= floor, "{} < {}", last, floor);
+        }
+    }
+}
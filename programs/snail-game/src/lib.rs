@@ -1,6 +1,30 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{self, Mint, Token2022, TokenAccount, FreezeAccount};
+use anchor_lang::Ids;
+use anchor_spl::token_interface::{
+    self, Approve, Burn, FreezeAccount, Mint, Token2022, TokenAccount, TokenInterface,
+    TransferChecked,
+};
 use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_hook::{TransferHook as TransferHookExtension, TransferHookAccount},
+    BaseStateWithExtensions, StateWithExtensions,
+};
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+pub mod curve;
+pub mod market;
+pub mod math;
+pub mod outcome;
+pub mod price;
+pub mod shell;
+
+pub use curve::{CurveKind, CurveKnot, ExponentMode, BPS};
+pub use market::{Market, MarketSide, Position};
+pub use outcome::Outcome;
+pub use price::PriceSource;
+pub use shell::{Shell, Stake};
 
 declare_id!("2PgtpKBFjWgdk7wLxZD7xC8sc6qpsXmDw1dPKQnmdJPT");
 
@@ -8,336 +32,3675 @@ declare_id!("2PgtpKBFjWgdk7wLxZD7xC8sc6qpsXmDw1dPKQnmdJPT");
 pub mod snail_game {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         ctx: Context<Initialize>,
+        game_id: u64,
         snail_start_stamp: i64,
         snail_end_stamp: i64,
         target_market_cap: u64,
-        curve_factor: u64,
-        usdc_lp: Pubkey,
-        snail_lp: Pubkey,
-        snail_mint: Pubkey,
+        market_cap_decimals: u8,
+        curve: CurveKind,
+        twap_window: i64,
+        price_source: PriceSource,
+        grace_period: i64,
+        outcome: Outcome,
+        timing_mode: TimingMode,
     ) -> Result<()> {
+        validate_game_parameters(
+            snail_start_stamp,
+            snail_end_stamp,
+            target_market_cap,
+            market_cap_decimals,
+            &curve,
+            twap_window,
+            grace_period,
+            &outcome,
+            timing_mode.time_point(&Clock::get()?),
+        )?;
+        
+        // Without the freeze authority touch_snail could never freeze anything
         require!(
-            snail_end_stamp > snail_start_stamp,
-            SnailError::InvalidTimestamps
+            is_armed(&ctx.accounts.snail_mint, &ctx.accounts.freeze_authority),
+            SnailError::InvalidFreezeAuthority
         );
-        require!(curve_factor <= 100, SnailError::InvalidCurveFactor);
         
         let game_state = &mut ctx.accounts.game_state;
         game_state.owner = ctx.accounts.owner.key();
+        game_state.game_id = game_id;
         game_state.snail_start_stamp = snail_start_stamp;
         game_state.snail_end_stamp = snail_end_stamp;
         game_state.target_market_cap = target_market_cap;
-        game_state.curve_factor = curve_factor;
-        game_state.usdc_lp = usdc_lp;
-        game_state.snail_lp = snail_lp;
-        game_state.snail_mint = snail_mint;
-        game_state.configured = true;
+        game_state.curve = curve;
+        game_state.usdc_lp = ctx.accounts.usdc_lp.key();
+        game_state.snail_lp = ctx.accounts.snail_lp.key();
+        game_state.snail_mint = ctx.accounts.snail_mint.key();
+        game_state.usdc_mint = ctx.accounts.usdc_mint.key();
+        game_state.snail_decimals = ctx.accounts.snail_mint.decimals;
+        game_state.usdc_decimals = ctx.accounts.usdc_mint.decimals;
+        game_state.market_cap_decimals = market_cap_decimals;
+        game_state.twap_window = twap_window;
+        game_state.grace_period = grace_period;
+        game_state.outcome = outcome;
+        game_state.timing_mode = timing_mode;
+        game_state.price_source = price_source;
+        game_state.pool = ctx.accounts.pool.as_ref().map(|pool| pool.key()).unwrap_or_default();
+        
+        // The pool must be the one the price source expects, holding both LP accounts
+        require!(
+            price_source != PriceSource::TokenAccounts || ctx.accounts.pool.is_none(),
+            SnailError::InvalidPool
+        );
+        spot_market_cap(
+            game_state,
+            ctx.accounts.pool.as_deref(),
+            &ctx.accounts.usdc_lp,
+            &ctx.accounts.snail_lp,
+            &ctx.accounts.snail_mint,
+            ctx.remaining_accounts,
+        )?;
+        
+        let clock = Clock::get()?;
+        game_state.set_phase(GamePhase::Pending, clock.unix_timestamp);
+        game_state.sync_phase(&clock);
+        
+        ctx.accounts.observations.game_state = ctx.accounts.game_state.key();
         
         Ok(())
     }
 
-    /// Check the required market cap at a given timestamp
-    pub fn check_required_market_cap(ctx: Context<CheckRequiredMarketCap>, timestamp: i64) -> Result<u64> {
+    /// Dry run of `initialize`: validate the proposed parameters and return the
+    /// required market cap sampled at PREVIEW_SAMPLES evenly spaced time points,
+    /// from snail_start_stamp to the last time point before snail_end_stamp
+    #[allow(clippy::too_many_arguments)]
+    pub fn preview_game(
+        _ctx: Context<PreviewGame>,
+        snail_start_stamp: i64,
+        snail_end_stamp: i64,
+        target_market_cap: u64,
+        market_cap_decimals: u8,
+        curve: CurveKind,
+        twap_window: i64,
+        grace_period: i64,
+        outcome: Outcome,
+        timing_mode: TimingMode,
+    ) -> Result<Vec<CurvePoint>> {
+        validate_game_parameters(
+            snail_start_stamp,
+            snail_end_stamp,
+            target_market_cap,
+            market_cap_decimals,
+            &curve,
+            twap_window,
+            grace_period,
+            &outcome,
+            timing_mode.time_point(&Clock::get()?),
+        )?;
+        
+        curve_samples(&GameState {
+            snail_start_stamp,
+            snail_end_stamp,
+            target_market_cap,
+            market_cap_decimals,
+            curve,
+            timing_mode,
+            phase: GamePhase::Pending,
+            ..Default::default()
+        })
+    }
+
+    /// Check the required market cap at a given time point
+    /// (a unix timestamp or a slot, depending on the game's timing mode)
+    pub fn check_required_market_cap(
+        ctx: Context<CheckRequiredMarketCap>,
+        _game_id: u64,
+        time_point: i64,
+    ) -> Result<u64> {
+        // Return 0 if not configured, before start or after end
+        let required_market_cap = curve::required_market_cap(&ctx.accounts.game_state, time_point)?;
+        
+        Ok(required_market_cap as u64)
+    }
+
+    /// Check the current market cap, in units of 10^-market_cap_decimals USDC
+    pub fn check_current_market_cap(ctx: Context<CheckCurrentMarketCap>, _game_id: u64) -> Result<u64> {
         let game_state = &ctx.accounts.game_state;
+        require!(game_state.is_configured(), SnailError::NotConfigured);
         
-        // Return 0 if not configured or timestamps are invalid
-        if !game_state.configured || game_state.snail_end_stamp == 0 {
-            return Ok(0);
-        }
+        // Return 0 when the pool holds no SNAIL
+        let market_cap = spot_market_cap(
+            game_state,
+            ctx.accounts.pool.as_deref(),
+            &ctx.accounts.usdc_lp,
+            &ctx.accounts.snail_lp,
+            &ctx.accounts.snail_mint,
+            ctx.remaining_accounts,
+        )?
+        .unwrap_or(0);
+        
+        Ok(market_cap as u64)
+    }
+
+    /// Touch the snail - check if market cap is at or below required, and freeze if so
+    /// With a grace period configured, this only records the touch and the freeze
+    /// happens in `confirm_touch` once the period has passed.
+    pub fn touch_snail<'info>(
+        ctx: Context<'_, '_, '_, 'info, TouchSnail<'info>>,
+        game_id: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.game_state.sync_phase(&clock);
+        let game_state = &ctx.accounts.game_state;
+        
+        require!(game_state.is_configured(), SnailError::NotConfigured);
+        require!(game_state.phase != GamePhase::Touched, SnailError::AlreadyFrozen);
+        require!(game_state.phase != GamePhase::Survived, SnailError::AlreadySurvived);
+        require!(game_state.phase != GamePhase::TouchPending, SnailError::TouchPending);
         
-        // Return 0 if before start or after end
-        if timestamp < game_state.snail_start_stamp || timestamp >= game_state.snail_end_stamp {
-            return Ok(0);
+        let (current_market_cap, required_market_cap) = if game_state.phase == GamePhase::Breached {
+            // The transfer hook already saw the snail at or below the curve
+            (
+                game_state.breach_market_cap as u128,
+                game_state.breach_required_market_cap as u128,
+            )
+        } else {
+            let (current_market_cap, required_market_cap) = market_caps(
+                game_state,
+                &mut ctx.accounts.observations,
+                ctx.accounts.pool.as_deref(),
+                &ctx.accounts.usdc_lp,
+                &ctx.accounts.snail_lp,
+                &ctx.accounts.snail_mint,
+                ctx.remaining_accounts,
+                &clock,
+                game_state.time_point(&clock),
+            )?;
+            
+            require!(required_market_cap > 0, SnailError::InvalidTimestamps);
+            
+            // Only proceed if current is at or below required
+            require!(
+                current_market_cap <= required_market_cap,
+                SnailError::MarketCapTooHigh
+            );
+            
+            (current_market_cap, required_market_cap)
+        };
+        
+        if game_state.grace_period > 0 {
+            let game_state = &mut ctx.accounts.game_state;
+            game_state.touched_at = clock.unix_timestamp;
+            game_state.touch_time_point = game_state.time_point(&clock);
+            game_state.toucher = ctx.accounts.toucher.key();
+            game_state.set_phase(GamePhase::TouchPending, clock.unix_timestamp);
+            
+            emit!(SnailTouchPending {
+                game_id,
+                current_market_cap: current_market_cap as u64,
+                required_market_cap: required_market_cap as u64,
+                toucher: ctx.accounts.toucher.key(),
+                confirmable_at: clock.unix_timestamp + game_state.grace_period,
+            });
+            
+            return Ok(());
         }
         
-        // Calculate progress (0 to 1, scaled by 1e18 for precision)
-        let elapsed = (timestamp - game_state.snail_start_stamp) as u64;
-        let duration = (game_state.snail_end_stamp - game_state.snail_start_stamp) as u64;
-        let progress = ((elapsed as u128) * 1_000_000_000_000_000_000u128) / (duration as u128);
+        settle_touch(ctx, game_id, current_market_cap, required_market_cap)
+    }
+
+    /// Confirm a pending touch once the grace period has passed (anyone)
+    /// The snail must still be at or below the curve as it stood at the pending
+    /// touch, so a touch near the end can be confirmed after it. The bounty and slashed stakes
+    /// go to the caller of the pending touch, passed as `pending_toucher`.
+    pub fn confirm_touch<'info>(
+        ctx: Context<'_, '_, '_, 'info, TouchSnail<'info>>,
+        game_id: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let game_state = &ctx.accounts.game_state;
         
-        // Apply curve: progress^(1 + curveFactor * 0.4)
-        // curveFactor is stored with 1 decimal, so divide by 10
-        // Exponent = 1 + (curveFactor / 10) * 0.4 = 1 + curveFactor * 0.04
-        // Scaled: exponent = 1e18 + curveFactor * 4e16
-        let exponent = 1_000_000_000_000_000_000u128 + ((game_state.curve_factor as u128) * 400_000_000_000_000_00u128);
+        require!(game_state.phase == GamePhase::TouchPending, SnailError::NoPendingTouch);
+        require!(
+            clock.unix_timestamp >= game_state.touched_at + game_state.grace_period,
+            SnailError::GracePeriodActive
+        );
         
-        // Calculate curved progress
-        let curved_progress = pow(progress, exponent)?;
+        let (current_market_cap, required_market_cap) = market_caps(
+            game_state,
+            &mut ctx.accounts.observations,
+            ctx.accounts.pool.as_deref(),
+            &ctx.accounts.usdc_lp,
+            &ctx.accounts.snail_lp,
+            &ctx.accounts.snail_mint,
+            ctx.remaining_accounts,
+            &clock,
+            game_state.touch_time_point,
+        )?;
         
-        // Calculate required market cap
-        let required_market_cap = ((game_state.target_market_cap as u128) * curved_progress) / 1_000_000_000_000_000_000u128;
+        require!(required_market_cap > 0, SnailError::InvalidTimestamps);
+        require!(
+            current_market_cap <= required_market_cap,
+            SnailError::MarketCapTooHigh
+        );
         
-        Ok(required_market_cap as u64)
+        settle_touch(ctx, game_id, current_market_cap, required_market_cap)
     }
 
-    /// Check the current market cap
-    pub fn check_current_market_cap(ctx: Context<CheckCurrentMarketCap>) -> Result<u64> {
+    /// Reset a pending touch after the market cap recovered above the curve (anyone)
+    /// The curve is taken at the pending touch, as in `confirm_touch`.
+    pub fn clear_touch(ctx: Context<ClearTouch>, game_id: u64) -> Result<()> {
+        let clock = Clock::get()?;
         let game_state = &ctx.accounts.game_state;
-        require!(game_state.configured, SnailError::NotConfigured);
         
-        let usdc_lp_account = &ctx.accounts.usdc_lp;
-        let snail_lp_account = &ctx.accounts.snail_lp;
-        let snail_mint_account = &ctx.accounts.snail_mint;
+        require!(game_state.phase == GamePhase::TouchPending, SnailError::NoPendingTouch);
         
-        // Get reserves from LP token accounts
-        let snail_reserve = snail_lp_account.amount;
-        let usdc_reserve = usdc_lp_account.amount;
+        let (current_market_cap, required_market_cap) = market_caps(
+            game_state,
+            &mut ctx.accounts.observations,
+            ctx.accounts.pool.as_deref(),
+            &ctx.accounts.usdc_lp,
+            &ctx.accounts.snail_lp,
+            &ctx.accounts.snail_mint,
+            ctx.remaining_accounts,
+            &clock,
+            game_state.touch_time_point,
+        )?;
         
-        // Avoid division by zero
-        if snail_reserve == 0 {
-            return Ok(0);
-        }
+        require!(
+            current_market_cap > required_market_cap,
+            SnailError::MarketCapTooLow
+        );
         
-        // Calculate market cap: (usdcReserve * totalSupply) / snailReserve
-        let total_supply = snail_mint_account.supply;
-        let market_cap = ((usdc_reserve as u128) * (total_supply as u128)) / (snail_reserve as u128);
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.touched_at = 0;
+        game_state.touch_time_point = 0;
+        game_state.toucher = Pubkey::default();
+        game_state.set_phase(GamePhase::Live, clock.unix_timestamp);
         
-        Ok(market_cap as u64)
+        emit!(SnailTouchCleared {
+            game_id,
+            current_market_cap: current_market_cap as u64,
+            required_market_cap: required_market_cap as u64,
+        });
+        
+        Ok(())
     }
 
-    /// Touch the snail - check if market cap is at or below required, and freeze if so
-    pub fn touch_snail(ctx: Context<TouchSnail>) -> Result<()> {
-        let game_state = &ctx.accounts.game_state;
+    /// Declare that the snail survived (anyone, after snail_end_stamp)
+    /// A breach recorded by the transfer hook before the end still has to be
+    /// settled by `touch_snail`. Only the owner may also renounce the mint's freeze
+    /// authority, which makes the pool unfreezable forever.
+    pub fn declare_victory(
+        ctx: Context<DeclareVictory>,
+        game_id: u64,
+        renounce_freeze_authority: bool,
+    ) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
         let clock = Clock::get()?;
+        game_state.sync_phase(&clock);
+        
+        require!(game_state.is_configured(), SnailError::NotConfigured);
+        require!(game_state.phase != GamePhase::Touched, SnailError::AlreadyFrozen);
+        require!(game_state.phase != GamePhase::Survived, SnailError::AlreadySurvived);
+        require!(game_state.phase != GamePhase::Breached, SnailError::PendingBreach);
+        require!(game_state.phase != GamePhase::TouchPending, SnailError::TouchPending);
+        require!(
+            game_state.time_point(&clock) >= game_state.snail_end_stamp,
+            SnailError::GameNotOver
+        );
+        
+        game_state.survived_at = clock.unix_timestamp;
+        game_state.set_phase(GamePhase::Survived, clock.unix_timestamp);
+        
+        if renounce_freeze_authority {
+            require_keys_eq!(
+                ctx.accounts.caller.key(),
+                game_state.owner,
+                SnailError::Unauthorized
+            );
+            
+            let game_id_bytes = game_id.to_le_bytes();
+            let seeds = &[
+                b"freeze-authority".as_ref(),
+                game_id_bytes.as_ref(),
+                &[ctx.bumps.freeze_authority],
+            ];
+            let signer = &[&seeds[..]];
+            
+            token_2022::set_authority(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_2022::SetAuthority {
+                        current_authority: ctx.accounts.freeze_authority.to_account_info(),
+                        account_or_mint: ctx.accounts.snail_mint.to_account_info(),
+                    },
+                    signer,
+                ),
+                AuthorityType::FreezeAccount,
+                None,
+            )?;
+        }
+        
+        emit!(SnailSurvived {
+            game_id,
+            timestamp: clock.unix_timestamp,
+            freeze_authority_renounced: renounce_freeze_authority,
+        });
         
-        require!(game_state.configured, SnailError::NotConfigured);
-        require!(!game_state.frozen, SnailError::AlreadyFrozen);
+        Ok(())
+    }
+
+    /// Set up the toucher bounty vault (game owner, once)
+    /// Anyone can fund the vault afterwards with a plain token transfer. A successful
+    /// `touch_snail` pays either a fixed `amount` or `bps` of the vault balance.
+    pub fn initialize_bounty(
+        ctx: Context<InitializeBounty>,
+        _game_id: u64,
+        amount: u64,
+        bps: u16,
+    ) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
         
-        // Calculate current market cap
-        let usdc_lp_account = &ctx.accounts.usdc_lp;
-        let snail_lp_account = &ctx.accounts.snail_lp;
-        let snail_mint_account = &ctx.accounts.snail_mint;
+        require!(game_state.phase != GamePhase::Touched, SnailError::AlreadyFrozen);
+        require!(game_state.phase != GamePhase::Survived, SnailError::AlreadySurvived);
+        require!(!game_state.has_bounty(), SnailError::BountyAlreadyConfigured);
+        require!(
+            (amount > 0) != (bps > 0) && bps <= BPS,
+            SnailError::InvalidBounty
+        );
         
-        let snail_reserve = snail_lp_account.amount;
-        let usdc_reserve = usdc_lp_account.amount;
+        game_state.bounty_mint = ctx.accounts.bounty_mint.key();
+        game_state.bounty_amount = amount;
+        game_state.bounty_bps = bps;
         
-        require!(snail_reserve > 0, SnailError::InvalidReserves);
+        emit!(BountyConfigured {
+            game_id: game_state.game_id,
+            bounty_mint: game_state.bounty_mint,
+            bounty_vault: ctx.accounts.bounty_vault.key(),
+            amount,
+            bps,
+        });
         
-        let total_supply = snail_mint_account.supply;
-        let current_market_cap = ((usdc_reserve as u128) * (total_supply as u128)) / (snail_reserve as u128);
+        Ok(())
+    }
+
+    /// Set up the outcome vault settled by a touch (game owner, once)
+    /// Anyone can fund the vault afterwards with a plain token transfer. Rewards and
+    /// penalties cannot be paid in a mint whose transfers run this program's hook
+    /// (see `routes_through_this_program`).
+    pub fn initialize_outcome_vault(ctx: Context<InitializeOutcomeVault>, _game_id: u64) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
         
-        // Calculate required market cap at current time
-        let timestamp = clock.unix_timestamp;
-        let required_market_cap = if timestamp < game_state.snail_start_stamp || timestamp >= game_state.snail_end_stamp {
-            0u128
-        } else {
-            // Calculate progress (0 to 1, scaled by 1e18 for precision)
-            let elapsed = (timestamp - game_state.snail_start_stamp) as u64;
-            let duration = (game_state.snail_end_stamp - game_state.snail_start_stamp) as u64;
-            let progress = ((elapsed as u128) * 1_000_000_000_000_000_000u128) / (duration as u128);
-            
-            // Apply curve: progress^(1 + curveFactor * 0.4)
-            let exponent = 1_000_000_000_000_000_000u128 + ((game_state.curve_factor as u128) * 400_000_000_000_000_00u128);
-            let curved_progress = pow(progress, exponent)?;
+        require!(game_state.phase != GamePhase::Touched, SnailError::AlreadyFrozen);
+        require!(game_state.phase != GamePhase::Survived, SnailError::AlreadySurvived);
+        require!(game_state.outcome.uses_vault(), SnailError::InvalidOutcome);
+        require!(
+            game_state.outcome_mint == Pubkey::default(),
+            SnailError::OutcomeVaultAlreadyConfigured
+        );
+        require!(
+            game_state.outcome == Outcome::BurnTreasury
+                || !routes_through_this_program(&ctx.accounts.outcome_mint)?,
+            SnailError::InvalidOutcomeMint
+        );
+        
+        game_state.outcome_mint = ctx.accounts.outcome_mint.key();
+        
+        emit!(OutcomeVaultConfigured {
+            game_id: game_state.game_id,
+            outcome: game_state.outcome,
+            outcome_mint: game_state.outcome_mint,
+            outcome_vault: ctx.accounts.outcome_vault.key(),
+        });
+        
+        Ok(())
+    }
+
+    /// Burn `amount` SNAIL for a share of the released rewards (any holder)
+    /// The share is `amount` over the SNAIL supply at the touch.
+    pub fn claim_reward(ctx: Context<ClaimReward>, game_id: u64, amount: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        
+        require!(game_state.phase == GamePhase::Touched, SnailError::RewardsNotReleased);
+        let payout = outcome::reward_payout(game_state, amount)?.min(ctx.accounts.outcome_vault.amount);
+        
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.snail_mint.to_account_info(),
+                    from: ctx.accounts.holder_snail_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        if payout > 0 {
+            let game_id_bytes = game_id.to_le_bytes();
+            let seeds = &[
+                b"game_state".as_ref(),
+                game_id_bytes.as_ref(),
+                &[ctx.bumps.game_state],
+            ];
+            let signer = &[&seeds[..]];
             
-            // Calculate required market cap
-            ((game_state.target_market_cap as u128) * curved_progress) / 1_000_000_000_000_000_000u128
-        };
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.outcome_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.outcome_vault.to_account_info(),
+                        mint: ctx.accounts.outcome_mint.to_account_info(),
+                        to: ctx.accounts.holder_reward_account.to_account_info(),
+                        authority: ctx.accounts.game_state.to_account_info(),
+                    },
+                    signer,
+                ),
+                payout,
+                ctx.accounts.outcome_mint.decimals,
+            )?;
+        }
         
-        require!(required_market_cap > 0, SnailError::InvalidTimestamps);
+        emit!(RewardClaimed {
+            game_id,
+            holder: ctx.accounts.holder.key(),
+            burned: amount,
+            payout,
+        });
+        
+        Ok(())
+    }
+
+    /// Set up the defender staking pool and its reward vault (game owner, once)
+    /// Anyone can fund the reward vault with a plain token transfer. Rewards cannot
+    /// be paid in a mint whose transfers run this program's hook (see
+    /// `routes_through_this_program`).
+    pub fn initialize_shell(ctx: Context<InitializeShell>, _game_id: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
         
-        // Only proceed if current is at or below required
+        require!(game_state.phase != GamePhase::Touched, SnailError::AlreadyFrozen);
+        require!(game_state.phase != GamePhase::Survived, SnailError::AlreadySurvived);
         require!(
-            current_market_cap <= required_market_cap,
-            SnailError::MarketCapTooHigh
+            !routes_through_this_program(&ctx.accounts.reward_mint)?,
+            SnailError::InvalidRewardMint
+        );
+        
+        let shell = &mut ctx.accounts.shell;
+        shell.game_state = game_state.key();
+        shell.reward_mint = ctx.accounts.reward_mint.key();
+        shell.last_update = game_state.time_point(&Clock::get()?);
+        
+        emit!(ShellInitialized {
+            game_id: game_state.game_id,
+            reward_mint: shell.reward_mint,
+            reward_vault: ctx.accounts.reward_vault.key(),
+        });
+        
+        Ok(())
+    }
+
+    /// Create the caller's stake account and SNAIL stake vault
+    pub fn open_stake(ctx: Context<OpenStake>, _game_id: u64) -> Result<()> {
+        let stake = &mut ctx.accounts.stake;
+        stake.owner = ctx.accounts.owner.key();
+        stake.game_state = ctx.accounts.game_state.key();
+        stake.last_update = ctx.accounts.game_state.time_point(&Clock::get()?);
+        
+        Ok(())
+    }
+
+    /// Stake the SNAIL transferred into the caller's stake vault since the last call
+    /// Open until `snail_end_stamp`. Deposits are plain Token-2022 transfers into the
+    /// vault (see `routes_through_this_program`).
+    pub fn stake(ctx: Context<StakeSnail>, _game_id: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        let time_point = game_state.time_point(&Clock::get()?);
+        
+        require!(
+            matches!(game_state.phase_at(time_point), GamePhase::Pending | GamePhase::Live)
+                && time_point < game_state.snail_end_stamp,
+            SnailError::StakingClosed
         );
         
-        // Mark as frozen
-        let game_state_mut = &mut ctx.accounts.game_state;
-        game_state_mut.frozen = true;
+        // Tokens approved for withdrawal are no longer staked
+        let stake_vault = &ctx.accounts.stake_vault;
+        let balance = stake_vault.amount.saturating_sub(stake_vault.delegated_amount);
+        let stake = &mut ctx.accounts.stake;
+        require!(balance > stake.amount, SnailError::NothingToStake);
+        let amount = balance - stake.amount;
+        
+        let shell = &mut ctx.accounts.shell;
+        shell.accrue(game_state, time_point)?;
+        stake.accrue(game_state, time_point)?;
+        shell.total_staked = shell.total_staked.checked_add(amount).ok_or(SnailError::MathOverflow)?;
+        stake.amount = balance;
+        
+        emit!(Staked {
+            game_id: game_state.game_id,
+            owner: stake.owner,
+            amount,
+            total_staked: shell.total_staked,
+        });
+        
+        Ok(())
+    }
+
+    /// Release `amount` of the caller's stake, before the start or after survival
+    /// The owner is approved as the stake vault's delegate and pulls the SNAIL with
+    /// a plain transfer. After a touch, the owner can only approve the withdrawals
+    /// left in the vault, once the toucher has pulled the slashed stake.
+    pub fn unstake(ctx: Context<Unstake>, _game_id: u64, amount: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        let time_point = game_state.time_point(&Clock::get()?);
+        
+        let stake_vault = &ctx.accounts.stake_vault;
+        match game_state.phase_at(time_point) {
+            GamePhase::Pending | GamePhase::Survived => {}
+            GamePhase::Touched => {
+                let slash_pulled = !stake_vault.delegate.contains(&game_state.toucher)
+                    || stake_vault.delegated_amount == 0;
+                require!(ctx.accounts.stake.amount == 0, SnailError::StakeLocked);
+                require!(slash_pulled, SnailError::SlashNotPulled);
+            }
+            _ => return err!(SnailError::StakeLocked),
+        }
+        
+        let stake = &mut ctx.accounts.stake;
+        require!(amount <= stake.amount, SnailError::InsufficientStake);
         
-        // Freeze the snail LP account
+        let shell = &mut ctx.accounts.shell;
+        shell.accrue(game_state, time_point)?;
+        stake.accrue(game_state, time_point)?;
+        shell.total_staked -= amount;
+        stake.amount -= amount;
+        
+        let game_state_key = game_state.key();
+        let owner_key = stake.owner;
         let seeds = &[
-            b"freeze-authority".as_ref(),
-            &[ctx.bumps.freeze_authority],
+            b"stake".as_ref(),
+            game_state_key.as_ref(),
+            owner_key.as_ref(),
+            &[ctx.bumps.stake],
         ];
         let signer = &[&seeds[..]];
         
-        token_interface::freeze_account(
+        token_interface::approve(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                FreezeAccount {
-                    account: ctx.accounts.snail_lp.to_account_info(),
-                    mint: ctx.accounts.snail_mint.to_account_info(),
-                    authority: ctx.accounts.freeze_authority.to_account_info(),
+                Approve {
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    delegate: ctx.accounts.owner.to_account_info(),
+                    authority: ctx.accounts.stake.to_account_info(),
                 },
                 signer,
             ),
+            ctx.accounts.stake.pending_withdrawal(ctx.accounts.stake_vault.amount),
         )?;
         
-        // Renounce freeze authority (set to None)
-        token_2022::set_authority(
+        emit!(Unstaked {
+            game_id: game_state.game_id,
+            owner: owner_key,
+            amount,
+            total_staked: ctx.accounts.shell.total_staked,
+        });
+        
+        Ok(())
+    }
+
+    /// Claim the caller's share of the shell rewards after the game survived
+    /// Shares are by SNAIL-seconds staked inside the game window.
+    pub fn claim_shell_reward(ctx: Context<ClaimShellReward>, game_id: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        let time_point = game_state.time_point(&Clock::get()?);
+        
+        require!(game_state.phase == GamePhase::Survived, SnailError::GameNotOver);
+        require!(!ctx.accounts.stake.reward_claimed, SnailError::RewardAlreadyClaimed);
+        
+        let shell = &mut ctx.accounts.shell;
+        let stake = &mut ctx.accounts.stake;
+        shell.accrue(game_state, time_point)?;
+        stake.accrue(game_state, time_point)?;
+        
+        let payout = shell
+            .reward_payout(stake, ctx.accounts.reward_vault.amount)?
+            .min(ctx.accounts.reward_vault.amount);
+        stake.reward_claimed = true;
+        
+        if payout > 0 {
+            let game_id_bytes = game_id.to_le_bytes();
+            let seeds = &[
+                b"game_state".as_ref(),
+                game_id_bytes.as_ref(),
+                &[ctx.bumps.game_state],
+            ];
+            let signer = &[&seeds[..]];
+            
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.reward_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        mint: ctx.accounts.reward_mint.to_account_info(),
+                        to: ctx.accounts.owner_reward_account.to_account_info(),
+                        authority: ctx.accounts.game_state.to_account_info(),
+                    },
+                    signer,
+                ),
+                payout,
+                ctx.accounts.reward_mint.decimals,
+            )?;
+        }
+        
+        emit!(ShellRewardClaimed {
+            game_id,
+            owner: ctx.accounts.stake.owner,
+            payout,
+        });
+        
+        Ok(())
+    }
+
+    /// Return the shell's reward vault to the game owner when no staker can claim
+    /// it: after a touch, or after survival with no stake inside the game window
+    pub fn sweep_shell_rewards(ctx: Context<SweepShellRewards>, game_id: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        let time_point = game_state.time_point(&Clock::get()?);
+        
+        let shell = &mut ctx.accounts.shell;
+        shell.accrue(game_state, time_point)?;
+        require!(
+            game_state.phase == GamePhase::Touched
+                || (game_state.phase == GamePhase::Survived && shell.total_stake_seconds == 0),
+            SnailError::ShellRewardsClaimable
+        );
+        
+        let amount = ctx.accounts.reward_vault.amount;
+        if amount > 0 {
+            let game_id_bytes = game_id.to_le_bytes();
+            let seeds = &[
+                b"game_state".as_ref(),
+                game_id_bytes.as_ref(),
+                &[ctx.bumps.game_state],
+            ];
+            let signer = &[&seeds[..]];
+            
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.reward_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        mint: ctx.accounts.reward_mint.to_account_info(),
+                        to: ctx.accounts.owner_reward_account.to_account_info(),
+                        authority: ctx.accounts.game_state.to_account_info(),
+                    },
+                    signer,
+                ),
+                amount,
+                ctx.accounts.reward_mint.decimals,
+            )?;
+        }
+        
+        emit!(ShellRewardsSwept { game_id, amount });
+        
+        Ok(())
+    }
+
+    /// Slash a stake to the toucher once the snail is touched (anyone)
+    /// The toucher is approved as the stake vault's delegate for the staked amount
+    /// only. A vault has a single delegate, so this replaces the approval of a
+    /// withdrawal the owner never pulled; `unstake` approves it again once the
+    /// toucher has pulled the slash.
+    pub fn slash_stake(ctx: Context<SlashStake>, _game_id: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        require!(game_state.phase == GamePhase::Touched, SnailError::GameNotTouched);
+        
+        let slashed = ctx.accounts.stake.slash(&mut ctx.accounts.shell)?;
+        
+        let stake = &ctx.accounts.stake;
+        let game_state_key = game_state.key();
+        let owner_key = stake.owner;
+        let seeds = &[
+            b"stake".as_ref(),
+            game_state_key.as_ref(),
+            owner_key.as_ref(),
+            &[ctx.bumps.stake],
+        ];
+        let signer = &[&seeds[..]];
+        
+        token_interface::approve(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                token_2022::SetAuthority {
-                    current_authority: ctx.accounts.freeze_authority.to_account_info(),
-                    account_or_mint: ctx.accounts.snail_mint.to_account_info(),
+                Approve {
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    delegate: ctx.accounts.toucher.to_account_info(),
+                    authority: ctx.accounts.stake.to_account_info(),
                 },
                 signer,
             ),
-            AuthorityType::FreezeAccount,
-            None, // Revoke (set to None)
+            slashed,
         )?;
         
-        emit!(SnailTouched {
-            current_market_cap: current_market_cap as u64,
-            required_market_cap: required_market_cap as u64,
+        emit!(StakeSlashed {
+            game_id: game_state.game_id,
+            owner: owner_key,
+            toucher: game_state.toucher,
+            amount: slashed,
         });
         
         Ok(())
     }
-}
 
-/// Internal helper function for power calculation (exact copy of Solidity _pow)
-fn pow(base: u128, exponent: u128) -> Result<u128> {
-    // Handle edge cases
-    if base == 0 {
-        return Ok(0);
-    }
-    if exponent == 0 {
-        return Ok(1_000_000_000_000_000_000u128);
-    }
-    if exponent == 1_000_000_000_000_000_000u128 {
-        return Ok(base);
+    /// Open the game's prediction market, taking deposits until `cutoff` (game owner, once)
+    /// The cutoff must leave the last tenth of the game window, see `Market::latest_cutoff`.
+    pub fn initialize_market(ctx: Context<InitializeMarket>, _game_id: u64, cutoff: i64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        let time_point = game_state.time_point(&Clock::get()?);
+        
+        require!(game_state.is_configured(), SnailError::NotConfigured);
+        require!(
+            cutoff > time_point
+                && cutoff <= Market::latest_cutoff(game_state.snail_start_stamp, game_state.snail_end_stamp),
+            SnailError::InvalidMarketCutoff
+        );
+        
+        let market = &mut ctx.accounts.market;
+        market.game_state = game_state.key();
+        market.cutoff = cutoff;
+        
+        emit!(MarketInitialized {
+            game_id: game_state.game_id,
+            market_vault: ctx.accounts.market_vault.key(),
+            cutoff,
+        });
+        
+        Ok(())
     }
-    if base == 1_000_000_000_000_000_000u128 {
-        return Ok(1_000_000_000_000_000_000u128);
+
+    /// Create the caller's position in the prediction market
+    pub fn open_position(ctx: Context<OpenPosition>, _game_id: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.market = ctx.accounts.market.key();
+        
+        Ok(())
     }
-    
-    let integer_part = exponent / 1_000_000_000_000_000_000u128;
-    let fractional_part = exponent % 1_000_000_000_000_000_000u128;
-    
-    // Start with base^integerPart
-    let mut result = 1_000_000_000_000_000_000u128;
-    for _ in 0..integer_part {
-        result = result
-            .checked_mul(base)
-            .ok_or(SnailError::MathOverflow)?
-            / 1_000_000_000_000_000_000u128;
+
+    /// Deposit `amount` USDC on `side`, until the cutoff and while the game is running
+    /// A live game must be above the curve, measured as `touch_snail` would, so a
+    /// deposit cannot be followed by a winning touch in the same transaction.
+    pub fn deposit_prediction<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositPrediction<'info>>,
+        _game_id: u64,
+        side: MarketSide,
+        amount: u64,
+    ) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        let clock = Clock::get()?;
+        let time_point = game_state.time_point(&clock);
+        let phase = game_state.phase_at(time_point);
+        
+        let market_caps = if phase == GamePhase::Live {
+            Some(market_caps(
+                game_state,
+                &mut ctx.accounts.observations,
+                ctx.accounts.pool.as_deref(),
+                &ctx.accounts.usdc_lp,
+                &ctx.accounts.snail_lp,
+                &ctx.accounts.snail_mint,
+                ctx.remaining_accounts,
+                &clock,
+                time_point,
+            )?)
+        } else {
+            None
+        };
+        ctx.accounts.market.check_deposit(phase, time_point, market_caps)?;
+        require!(amount > 0, SnailError::InvalidDeposit);
+        
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.owner_usdc_account.to_account_info(),
+                    mint: ctx.accounts.usdc_mint.to_account_info(),
+                    to: ctx.accounts.market_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.usdc_mint.decimals,
+        )?;
+        
+        ctx.accounts.market.deposit(&mut ctx.accounts.position, side, amount)?;
+        
+        emit!(PredictionPlaced {
+            game_id: game_state.game_id,
+            owner: ctx.accounts.owner.key(),
+            side,
+            amount,
+        });
+        
+        Ok(())
     }
-    
-    // For fractional part, use linear interpolation between base^n and base^(n+1)
-    if fractional_part > 0 {
-        let next_power = result
-            .checked_mul(base)
-            .ok_or(SnailError::MathOverflow)?
-            / 1_000_000_000_000_000_000u128;
-        let diff = result
-            .checked_sub(next_power)
-            .ok_or(SnailError::MathOverflow)?;
-        result = result
-            .checked_sub((diff.checked_mul(fractional_part).ok_or(SnailError::MathOverflow)?) / 1_000_000_000_000_000_000u128)
-            .ok_or(SnailError::MathOverflow)?;
+
+    /// Claim the caller's winnings once the game is touched or has survived
+    pub fn claim_prediction(ctx: Context<ClaimPrediction>, game_id: u64) -> Result<()> {
+        require!(!ctx.accounts.position.claimed, SnailError::RewardAlreadyClaimed);
+        
+        let payout = ctx
+            .accounts
+            .market
+            .payout(&ctx.accounts.position, ctx.accounts.game_state.phase)?
+            .min(ctx.accounts.market_vault.amount);
+        ctx.accounts.position.claimed = true;
+        
+        if payout > 0 {
+            let game_id_bytes = game_id.to_le_bytes();
+            let seeds = &[
+                b"game_state".as_ref(),
+                game_id_bytes.as_ref(),
+                &[ctx.bumps.game_state],
+            ];
+            let signer = &[&seeds[..]];
+            
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.market_vault.to_account_info(),
+                        mint: ctx.accounts.usdc_mint.to_account_info(),
+                        to: ctx.accounts.owner_usdc_account.to_account_info(),
+                        authority: ctx.accounts.game_state.to_account_info(),
+                    },
+                    signer,
+                ),
+                payout,
+                ctx.accounts.usdc_mint.decimals,
+            )?;
+        }
+        
+        emit!(PredictionClaimed {
+            game_id,
+            owner: ctx.accounts.owner.key(),
+            payout,
+        });
+        
+        Ok(())
     }
-    
-    Ok(result)
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + GameState::LEN,
-        seeds = [b"game_state"],
-        bump
+    /// Start the next season of a finished game as `next_game_id` (game owner)
+    /// The new game copies the previous configuration (mints, pools, curve, supply,
+    /// timing and outcome), scales the target by `target_multiplier_bps` and runs
+    /// for `duration` from `snail_start_stamp`, with the same checks as `initialize`.
+    /// A game has at most one next season. If the transfer hook is set up, it is
+    /// pointed at the new game. The previous game must still hold the freeze
+    /// authority, which a touch keeps and only `declare_victory` can renounce. It is
+    /// handed to the new game's freeze authority PDA.
+    pub fn start_next_season(
+        ctx: Context<StartNextSeason>,
+        game_id: u64,
+        next_game_id: u64,
+        target_multiplier_bps: u32,
+        snail_start_stamp: i64,
+        duration: i64,
+    ) -> Result<()> {
+        let previous = &mut ctx.accounts.previous_game_state;
+        let next_season = previous.next_season(
+            next_game_id,
+            target_multiplier_bps,
+            snail_start_stamp,
+            duration,
+            previous.time_point(&Clock::get()?),
+        )?;
+        let target_market_cap = next_season.target_market_cap;
+        let snail_end_stamp = next_season.snail_end_stamp;
+        previous.next_game_id = Some(next_game_id);
+        
+        ctx.accounts.game_state.set_inner(next_season);
+        
+        let clock = Clock::get()?;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.set_phase(GamePhase::Pending, clock.unix_timestamp);
+        game_state.sync_phase(&clock);
+        
+        ctx.accounts.observations.game_state = game_state.key();
+        
+        // The list is per mint: re-point it from the previous game to this one
+        let extra_account_meta_list = &ctx.accounts.extra_account_meta_list;
+        if !extra_account_meta_list.data_is_empty() {
+            ExtraAccountMetaList::update::<ExecuteInstruction>(
+                &mut extra_account_meta_list.try_borrow_mut_data()?,
+                &TransferHook::extra_account_metas(game_state)?,
+            )?;
+        }
+        
+        // Without the freeze authority touch_snail could never freeze anything
+        require!(
+            is_armed(&ctx.accounts.snail_mint, &ctx.accounts.previous_freeze_authority),
+            SnailError::InvalidFreezeAuthority
+        );
+        let game_id_bytes = game_id.to_le_bytes();
+        let seeds = &[
+            b"freeze-authority".as_ref(),
+            game_id_bytes.as_ref(),
+            &[ctx.bumps.previous_freeze_authority],
+        ];
+        let signer = &[&seeds[..]];
+        
+        token_2022::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_2022::SetAuthority {
+                    current_authority: ctx.accounts.previous_freeze_authority.to_account_info(),
+                    account_or_mint: ctx.accounts.snail_mint.to_account_info(),
+                },
+                signer,
+            ),
+            AuthorityType::FreezeAccount,
+            Some(ctx.accounts.freeze_authority.key()),
+        )?;
+        ctx.accounts.snail_mint.reload()?;
+        require!(
+            is_armed(&ctx.accounts.snail_mint, &ctx.accounts.freeze_authority),
+            SnailError::InvalidFreezeAuthority
+        );
+        
+        emit!(SeasonStarted {
+            game_id: next_game_id,
+            previous_game_id: game_id,
+            season: game_state.season,
+            target_market_cap,
+            snail_start_stamp,
+            snail_end_stamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Add a secondary SNAIL/USDC pool to the market cap (game owner, before the start)
+    /// Pools are fixed once the transfer hook's extra-account-meta list exists,
+    /// since the hook has to be handed every pool on each transfer. A secondary pool
+    /// must be an AMM pool whose state account lists both vaults: a pair of plain
+    /// token accounts could be funded by anyone to outweigh the real pools.
+    pub fn add_pool(ctx: Context<AddPool>, _game_id: u64, price_source: PriceSource) -> Result<()> {
+        let clock = Clock::get()?;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.sync_phase(&clock);
+        
+        require!(game_state.phase == GamePhase::Pending, SnailError::GameStarted);
+        require!(
+            ctx.accounts.extra_account_meta_list.data_is_empty(),
+            SnailError::PoolsLocked
+        );
+        require!(game_state.pools.len() < MAX_POOLS, SnailError::TooManyPools);
+        require!(
+            price_source != PriceSource::TokenAccounts && ctx.accounts.pool.is_some(),
+            SnailError::MissingPool
+        );
+        
+        let usdc_vault = ctx.accounts.usdc_vault.key();
+        let snail_vault = ctx.accounts.snail_vault.key();
+        require!(
+            usdc_vault != game_state.usdc_lp
+                && snail_vault != game_state.snail_lp
+                && !game_state
+                    .pools
+                    .iter()
+                    .any(|pool| pool.usdc_vault == usdc_vault || pool.snail_vault == snail_vault),
+            SnailError::DuplicatePool
+        );
+        
+        pool_price(
+            price_source,
+            ctx.accounts.pool.as_deref(),
+            vault(&ctx.accounts.usdc_vault),
+            vault(&ctx.accounts.snail_vault),
+        )?;
+        
+        let config = PoolConfig {
+            price_source,
+            pool: ctx.accounts.pool.as_ref().map(|pool| pool.key()).unwrap_or_default(),
+            usdc_vault,
+            snail_vault,
+        };
+        game_state.pools.push(config.clone());
+        
+        emit!(PoolAdded {
+            game_id: game_state.game_id,
+            price_source,
+            pool: config.pool,
+            usdc_vault,
+            snail_vault,
+        });
+        
+        Ok(())
+    }
+
+    /// Choose between fully diluted and circulating market cap (game owner, before the start)
+    /// In circulating mode the balances of `excluded_accounts` (treasury, locked or
+    /// burn addresses) are subtracted from the mint supply. The accounts are passed
+    /// as remaining accounts in the same order. Like pools, this is fixed once the
+    /// transfer hook's extra-account-meta list exists.
+    pub fn configure_supply(
+        ctx: Context<ConfigureSupply>,
+        _game_id: u64,
+        supply_mode: SupplyMode,
+        excluded_accounts: Vec<Pubkey>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.sync_phase(&clock);
+        
+        require!(game_state.phase == GamePhase::Pending, SnailError::GameStarted);
+        require!(
+            ctx.accounts.extra_account_meta_list.data_is_empty(),
+            SnailError::PoolsLocked
+        );
+        require!(
+            excluded_accounts.len() <= MAX_EXCLUDED_ACCOUNTS
+                && (supply_mode == SupplyMode::Circulating || excluded_accounts.is_empty()),
+            SnailError::InvalidExcludedAccount
+        );
+        require!(
+            ctx.remaining_accounts.len() == excluded_accounts.len(),
+            SnailError::MissingExcludedAccount
+        );
+        
+        for (i, (address, account)) in excluded_accounts.iter().zip(ctx.remaining_accounts).enumerate() {
+            require!(
+                !excluded_accounts[..i].contains(address),
+                SnailError::InvalidExcludedAccount
+            );
+            read_vault(account, address, &game_state.snail_mint, SnailError::InvalidExcludedAccount)?;
+        }
+        
+        game_state.supply_mode = supply_mode;
+        game_state.excluded_accounts = excluded_accounts;
+        
+        emit!(SupplyConfigured {
+            game_id: game_state.game_id,
+            supply_mode,
+            excluded_accounts: game_state.excluded_accounts.clone(),
+        });
+        
+        Ok(())
+    }
+
+    /// Set the extra token accounts frozen by a successful touch (game owner, before the start)
+    /// The snail LP and every secondary pool's SNAIL vault are always frozen; this
+    /// adds SNAIL accounts such as team wallets. The accounts are passed as remaining
+    /// accounts in the same order to check they hold SNAIL. Only games with the
+    /// `Freeze` outcome freeze anything.
+    pub fn configure_freeze_accounts(
+        ctx: Context<ConfigureFreezeAccounts>,
+        _game_id: u64,
+        freeze_accounts: Vec<Pubkey>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.sync_phase(&clock);
+        
+        require!(game_state.phase == GamePhase::Pending, SnailError::GameStarted);
+        require!(game_state.outcome == Outcome::Freeze, SnailError::InvalidOutcome);
+        require!(
+            freeze_accounts.len() <= MAX_FREEZE_ACCOUNTS,
+            SnailError::InvalidFreezeAccount
+        );
+        require!(
+            ctx.remaining_accounts.len() == freeze_accounts.len(),
+            SnailError::MissingFreezeAccount
+        );
+        
+        for (i, (address, account)) in freeze_accounts.iter().zip(ctx.remaining_accounts).enumerate() {
+            require!(
+                !freeze_accounts[..i].contains(address)
+                    && *address != game_state.snail_lp
+                    && !game_state.pools.iter().any(|pool| pool.snail_vault == *address),
+                SnailError::InvalidFreezeAccount
+            );
+            read_vault(account, address, &game_state.snail_mint, SnailError::InvalidFreezeAccount)?;
+        }
+        
+        game_state.freeze_accounts = freeze_accounts;
+        
+        emit!(FreezeAccountsConfigured {
+            game_id: game_state.game_id,
+            freeze_accounts: game_state.freeze_accounts.clone(),
+        });
+        
+        Ok(())
+    }
+
+    /// Create the ExtraAccountMetaList PDA that Token-2022 uses to resolve the
+    /// accounts passed to `transfer_hook` on every SNAIL transfer (game owner)
+    /// Its existence locks the pools and supply configuration, so only the owner
+    /// decides when that happens.
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+        _game_id: u64,
+    ) -> Result<()> {
+        require!(
+            routes_through_this_program(&ctx.accounts.snail_mint)?,
+            SnailError::InvalidTransferHook
+        );
+        
+        let extra_account_metas = TransferHook::extra_account_metas(&ctx.accounts.game_state)?;
+        
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas,
+        )?;
+        
+        Ok(())
+    }
+
+    /// Transfer hook - evaluate the snail after every SNAIL transfer
+    /// Token-2022 invokes this once the SNAIL has moved, but a swap may not have
+    /// moved its other leg yet. On a sell the USDC is still in the pool, so a
+    /// transfer into a pool's SNAIL vault is not recorded as an observation. The
+    /// freeze itself cannot happen here (Token-2022 cannot be re-entered), so a
+    /// breach of the TWAP is recorded in GameState and `touch_snail` performs the
+    /// freeze later. Games without a TWAP window are only evaluated by `touch_snail`.
+    /// Errors never leave the hook, since they would revert every SNAIL transfer.
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, _amount: u64) -> Result<()> {
+        assert_is_transferring(&ctx.accounts.source_token)?;
+        
+        let game_state = &mut ctx.accounts.game_state;
+        let clock = Clock::get()?;
+        let timestamp = clock.unix_timestamp;
+        game_state.sync_phase(&clock);
+        
+        // Never block transfers: just skip evaluation when there is nothing to do.
+        // Without a TWAP window there is nothing the hook can record safely: a spot
+        // price seen here can sit inside a single swap or flash loan.
+        if game_state.phase != GamePhase::Live || game_state.twap_window == 0 {
+            return Ok(());
+        }
+        
+        // Every other transfer doubles as an observation for the TWAP. A sell's
+        // USDC leg has not left the pool yet, so its spot price is inflated.
+        let observations = &mut ctx.accounts.observations;
+        if !game_state.is_pool_snail_vault(&ctx.accounts.destination_token.key()) {
+            let Ok(Some(spot_market_cap)) = spot_market_cap(
+                game_state,
+                ctx.accounts.pool.as_deref(),
+                &ctx.accounts.usdc_lp,
+                &ctx.accounts.snail_lp,
+                &ctx.accounts.mint,
+                ctx.remaining_accounts,
+            ) else {
+                return Ok(());
+            };
+            if observations
+                .record(timestamp, spot_market_cap, game_state.observation_interval())
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+        
+        // Only the TWAP can record a breach
+        let Ok(current_market_cap) = observations.twap(timestamp, game_state.twap_window) else {
+            return Ok(());
+        };
+        
+        let Ok(required_market_cap) = curve::required_market_cap(game_state, game_state.time_point(&clock)) else {
+            return Ok(());
+        };
+        
+        if required_market_cap == 0 || current_market_cap > required_market_cap {
+            return Ok(());
+        }
+        
+        game_state.breached_at = timestamp;
+        game_state.breach_market_cap = current_market_cap as u64;
+        game_state.breach_required_market_cap = required_market_cap as u64;
+        game_state.set_phase(GamePhase::Breached, timestamp);
+        
+        emit!(SnailBreached {
+            game_id: game_state.game_id,
+            current_market_cap: current_market_cap as u64,
+            required_market_cap: required_market_cap as u64,
+            timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Record a market cap observation for the TWAP (permissionless crank)
+    pub fn record_observation(ctx: Context<RecordObservation>, _game_id: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        require!(game_state.is_configured(), SnailError::NotConfigured);
+        
+        let spot_market_cap = spot_market_cap(
+            game_state,
+            ctx.accounts.pool.as_deref(),
+            &ctx.accounts.usdc_lp,
+            &ctx.accounts.snail_lp,
+            &ctx.accounts.snail_mint,
+            ctx.remaining_accounts,
+        )?
+        .ok_or(SnailError::InvalidReserves)?;
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        ctx.accounts.observations.record(
+            timestamp,
+            spot_market_cap,
+            game_state.observation_interval(),
+        )?;
+        
+        Ok(())
+    }
+
+    /// Create the game's checkpoint log (anyone, once)
+    pub fn initialize_checkpoint_log(ctx: Context<InitializeCheckpointLog>, _game_id: u64) -> Result<()> {
+        ctx.accounts.checkpoints.game_state = ctx.accounts.game_state.key();
+        
+        Ok(())
+    }
+
+    /// Append the current and required market caps to the checkpoint log (anyone)
+    /// The current market cap is the one touch_snail compares, as in get_game_status.
+    /// At most one checkpoint is written per slot.
+    pub fn checkpoint(ctx: Context<RecordCheckpoint>, _game_id: u64) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        require!(game_state.is_configured(), SnailError::NotConfigured);
+        
+        let clock = Clock::get()?;
+        let spot_market_cap = spot_market_cap(
+            game_state,
+            ctx.accounts.pool.as_deref(),
+            &ctx.accounts.usdc_lp,
+            &ctx.accounts.snail_lp,
+            &ctx.accounts.snail_mint,
+            ctx.remaining_accounts,
+        )?
+        .unwrap_or(0);
+        let current_market_cap = reported_market_cap(
+            game_state,
+            &ctx.accounts.observations,
+            spot_market_cap,
+            clock.unix_timestamp,
+        );
+        let required_market_cap = curve::required_market_cap(game_state, game_state.time_point(&clock))?;
+        
+        ctx.accounts.checkpoints.record(
+            clock.slot,
+            Checkpoint {
+                timestamp: clock.unix_timestamp,
+                current_market_cap: u64::try_from(current_market_cap).unwrap_or(u64::MAX),
+                required_market_cap: required_market_cap as u64,
+            },
+        )
+    }
+
+    /// Up to `count` most recent checkpoints, oldest first
+    /// Capped at MAX_CHECKPOINTS_RETURNED to fit in the return data.
+    pub fn get_checkpoints(
+        ctx: Context<GetCheckpoints>,
+        _game_id: u64,
+        count: u16,
+    ) -> Result<Vec<Checkpoint>> {
+        Ok(ctx.accounts.checkpoints.latest(count as usize))
+    }
+
+    /// Snapshot of the game for clients: phase, both market caps, the margin
+    /// between them, the time left until snail_end_stamp (seconds, or slots for a
+    /// `Slot` game) and whether the game is armed, i.e. its freeze authority PDA
+    /// can still freeze the SNAIL mint
+    pub fn get_game_status(ctx: Context<GetGameStatus>, _game_id: u64) -> Result<GameStatus> {
+        let game_state = &ctx.accounts.game_state;
+        require!(game_state.is_configured(), SnailError::NotConfigured);
+        
+        let clock = Clock::get()?;
+        
+        let spot_market_cap = spot_market_cap(
+            game_state,
+            ctx.accounts.pool.as_deref(),
+            &ctx.accounts.usdc_lp,
+            &ctx.accounts.snail_lp,
+            &ctx.accounts.snail_mint,
+            ctx.remaining_accounts,
+        )?
+        .unwrap_or(0);
+        let current_market_cap = reported_market_cap(
+            game_state,
+            &ctx.accounts.observations,
+            spot_market_cap,
+            clock.unix_timestamp,
+        );
+        
+        game_status(
+            game_state,
+            current_market_cap,
+            game_state.time_point(&clock),
+            is_armed(&ctx.accounts.snail_mint, &ctx.accounts.freeze_authority),
+        )
+    }
+}
+
+/// Parameter checks shared by `initialize` and `preview_game`
+/// `time_point` is the current time in the proposed timing mode.
+#[allow(clippy::too_many_arguments)]
+fn validate_game_parameters(
+    snail_start_stamp: i64,
+    snail_end_stamp: i64,
+    target_market_cap: u64,
+    market_cap_decimals: u8,
+    curve: &CurveKind,
+    twap_window: i64,
+    grace_period: i64,
+    outcome: &Outcome,
+    time_point: i64,
+) -> Result<()> {
+    require!(
+        snail_end_stamp > snail_start_stamp,
+        SnailError::InvalidTimestamps
+    );
+    require!(snail_start_stamp >= time_point, SnailError::StartInPast);
+    require!(target_market_cap > 0, SnailError::InvalidTargetMarketCap);
+    curve.validate()?;
+    require!(twap_window >= 0, SnailError::InvalidTwapWindow);
+    require!(grace_period >= 0, SnailError::InvalidGracePeriod);
+    outcome.validate()?;
+    require!(
+        market_cap_decimals <= MAX_MARKET_CAP_DECIMALS,
+        SnailError::InvalidMarketCapDecimals
+    );
+    
+    Ok(())
+}
+
+/// Required market cap sampled at PREVIEW_SAMPLES evenly spaced time points, from
+/// snail_start_stamp to the last time point before snail_end_stamp
+pub fn curve_samples(game_state: &GameState) -> Result<Vec<CurvePoint>> {
+    let last = (game_state.snail_end_stamp - game_state.snail_start_stamp - 1) as i128;
+    (0..PREVIEW_SAMPLES as i128)
+        .map(|i| {
+            let time_point = game_state.snail_start_stamp + (last * i / (PREVIEW_SAMPLES as i128 - 1)) as i64;
+            Ok(CurvePoint {
+                time_point,
+                required_market_cap: curve::required_market_cap(game_state, time_point)? as u64,
+            })
+        })
+        .collect()
+}
+
+/// Status returned by `get_game_status` at `time_point`, from the reported market cap
+pub fn game_status(
+    game_state: &GameState,
+    current_market_cap: u128,
+    time_point: i64,
+    armed: bool,
+) -> Result<GameStatus> {
+    let phase = game_state.phase_at(time_point);
+    let required_market_cap = curve::required_market_cap(game_state, time_point)?;
+    
+    let time_remaining = match phase {
+        GamePhase::Touched | GamePhase::Survived => 0,
+        _ => (game_state.snail_end_stamp - time_point).max(0),
+    };
+    
+    Ok(GameStatus {
+        phase,
+        current_market_cap: current_market_cap as u64,
+        required_market_cap: required_market_cap as u64,
+        margin: current_market_cap as i128 - required_market_cap as i128,
+        time_remaining,
+        armed,
+    })
+}
+
+/// Market cap reported to clients: same basis as touch_snail, falling back to
+/// spot while the TWAP window fills up
+pub fn reported_market_cap(
+    game_state: &GameState,
+    observations: &ObservationBuffer,
+    spot_market_cap: u128,
+    timestamp: i64,
+) -> u128 {
+    if game_state.twap_window > 0 {
+        observations
+            .twap(timestamp, game_state.twap_window)
+            .unwrap_or(spot_market_cap)
+    } else {
+        spot_market_cap
+    }
+}
+
+/// Spot market cap of `supply`, weighted by the USDC each pool holds
+/// `pools` holds the primary pool followed by `game_state.pools` in order.
+/// Returns None when no pool has liquidity.
+pub fn pools_market_cap(game_state: &GameState, pools: &[price::PoolSnapshot], supply: u64) -> Result<Option<u128>> {
+    require!(pools.len() == 1 + game_state.pools.len(), SnailError::MissingPool);
+    
+    let price_sources = [game_state.price_source]
+        .into_iter()
+        .chain(game_state.pools.iter().map(|config| config.price_source));
+    
+    // (price, weight) per pool with liquidity
+    let mut prices = Vec::with_capacity(pools.len());
+    for (price_source, snapshot) in price_sources.zip(pools) {
+        let pool = snapshot.pool.as_ref().map(|(owner, data)| (owner, *data));
+        if let Some(price) = price_source.price(pool, &snapshot.usdc_vault, &snapshot.snail_vault)? {
+            prices.push((price, snapshot.usdc_vault.amount as u128));
+        }
+    }
+    
+    Ok(price::weighted_market_cap(
+        &prices,
+        supply,
+        game_state.usdc_decimals,
+        game_state.market_cap_decimals,
+    ))
+}
+
+/// Current market cap at `clock` (the TWAP when the game has a window, recording
+/// an observation first) and required market cap at `time_point`
+#[allow(clippy::too_many_arguments)]
+fn market_caps(
+    game_state: &GameState,
+    observations: &mut ObservationBuffer,
+    pool: Option<&AccountInfo>,
+    usdc_lp: &InterfaceAccount<TokenAccount>,
+    snail_lp: &InterfaceAccount<TokenAccount>,
+    snail_mint: &InterfaceAccount<Mint>,
+    remaining_accounts: &[AccountInfo],
+    clock: &Clock,
+    time_point: i64,
+) -> Result<(u128, u128)> {
+    let spot_market_cap = spot_market_cap(
+        game_state,
+        pool,
+        usdc_lp,
+        snail_lp,
+        snail_mint,
+        remaining_accounts,
+    )?
+    .ok_or(SnailError::InvalidReserves)?;
+    
+    // With a TWAP window configured, a single-block price move is not enough:
+    // the average over the window has to be under the curve
+    let current_market_cap = if game_state.twap_window > 0 {
+        observations.record(clock.unix_timestamp, spot_market_cap, game_state.observation_interval())?;
+        observations.twap(clock.unix_timestamp, game_state.twap_window)?
+    } else {
+        spot_market_cap
+    };
+    
+    let required_market_cap = curve::required_market_cap(game_state, time_point)?;
+    
+    Ok((current_market_cap, required_market_cap))
+}
+
+/// Mark the game touched, settle its outcome and pay the bounty
+fn settle_touch<'info>(
+    ctx: Context<'_, '_, '_, 'info, TouchSnail<'info>>,
+    game_id: u64,
+    current_market_cap: u128,
+    required_market_cap: u128,
+) -> Result<()> {
+    // Fail with a clear error rather than at the freeze CPI
+    require!(
+        is_armed(&ctx.accounts.snail_mint, &ctx.accounts.freeze_authority),
+        SnailError::InvalidFreezeAuthority
+    );
+    
+    // A confirmed touch pays the caller of the pending touch, not whoever confirmed it
+    let toucher = if ctx.accounts.game_state.phase == GamePhase::TouchPending {
+        ctx.accounts
+            .pending_toucher
+            .as_ref()
+            .ok_or(SnailError::MissingPendingToucher)?
+            .to_account_info()
+    } else {
+        ctx.accounts.toucher.to_account_info()
+    };
+    
+    // Mark as touched
+    ctx.accounts.game_state.set_phase(GamePhase::Touched, Clock::get()?.unix_timestamp);
+    ctx.accounts.game_state.toucher = toucher.key();
+    
+    let game_id_bytes = game_id.to_le_bytes();
+    let seeds = &[
+        b"freeze-authority".as_ref(),
+        game_id_bytes.as_ref(),
+        &[ctx.bumps.freeze_authority],
+    ];
+    let signer = &[&seeds[..]];
+    let game_state_seeds = &[
+        b"game_state".as_ref(),
+        game_id_bytes.as_ref(),
+        &[ctx.bumps.game_state],
+    ];
+    let game_state_signer = &[&game_state_seeds[..]];
+    
+    let outcome = ctx.accounts.game_state.outcome;
+    let outcome_amount = match outcome {
+        Outcome::Freeze => {
+            freeze_accounts(ctx.accounts, ctx.remaining_accounts, signer)?;
+            0
+        }
+        Outcome::BurnTreasury => burn_treasury(ctx.accounts, game_state_signer)?,
+        Outcome::ReleaseRewards => release_rewards(ctx.accounts)?,
+        Outcome::TransferPenalty { .. } => transfer_penalty(ctx.accounts, game_state_signer)?,
+    };
+    
+    // The freeze authority stays with this game, which can no longer freeze or
+    // thaw anything, so start_next_season can still hand it to the next season
+    
+    // Pay the toucher bounty, if this game has one
+    let bounty_paid = if ctx.accounts.game_state.has_bounty() {
+        let (Some(bounty_vault), Some(bounty_mint), Some(toucher_token_account), Some(bounty_token_program)) = (
+            &ctx.accounts.bounty_vault,
+            &ctx.accounts.bounty_mint,
+            &ctx.accounts.toucher_token_account,
+            &ctx.accounts.bounty_token_program,
+        ) else {
+            return err!(SnailError::MissingBountyAccounts);
+        };
+        require_keys_eq!(
+            toucher_token_account.owner,
+            toucher.key(),
+            SnailError::InvalidToucherTokenAccount
+        );
+        
+        let payout = ctx.accounts.game_state.bounty_payout(bounty_vault.amount);
+        
+        if payout > 0 && routes_through_this_program(bounty_mint)? {
+            // See routes_through_this_program: the toucher pulls the payout
+            token_interface::approve(
+                CpiContext::new_with_signer(
+                    bounty_token_program.to_account_info(),
+                    Approve {
+                        to: bounty_vault.to_account_info(),
+                        delegate: toucher,
+                        authority: ctx.accounts.game_state.to_account_info(),
+                    },
+                    game_state_signer,
+                ),
+                payout,
+            )?;
+        } else if payout > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    bounty_token_program.to_account_info(),
+                    TransferChecked {
+                        from: bounty_vault.to_account_info(),
+                        mint: bounty_mint.to_account_info(),
+                        to: toucher_token_account.to_account_info(),
+                        authority: ctx.accounts.game_state.to_account_info(),
+                    },
+                    game_state_signer,
+                ),
+                payout,
+                bounty_mint.decimals,
+            )?;
+        }
+        
+        payout
+    } else {
+        0
+    };
+    
+    emit!(SnailTouched {
+        game_id,
+        current_market_cap: current_market_cap as u64,
+        required_market_cap: required_market_cap as u64,
+        toucher: ctx.accounts.game_state.toucher,
+        bounty_paid,
+    });
+    emit!(OutcomeSettled {
+        game_id,
+        outcome,
+        amount: outcome_amount,
+    });
+    
+    Ok(())
+}
+
+/// Freeze outcome: freeze the snail LP, every secondary pool's SNAIL vault (already
+/// in the market cap accounts) and the configured freeze accounts, which follow
+/// them in `remaining_accounts`
+fn freeze_accounts<'info>(
+    accounts: &TouchSnail<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let game_state = &accounts.game_state;
+    let market_cap_account_count = game_state.market_cap_account_count();
+    require!(
+        remaining_accounts.len() >= market_cap_account_count + game_state.freeze_accounts.len(),
+        SnailError::MissingFreezeAccount
+    );
+    let (market_cap_accounts, freeze_accounts) = remaining_accounts.split_at(market_cap_account_count);
+    
+    let mut targets = vec![accounts.snail_lp.to_account_info()];
+    for config in &game_state.pools {
+        let snail_vault = market_cap_accounts
+            .iter()
+            .find(|account| account.key() == config.snail_vault)
+            .ok_or(SnailError::MissingPool)?;
+        targets.push(snail_vault.clone());
+    }
+    for (address, account) in game_state.freeze_accounts.iter().zip(freeze_accounts) {
+        require_keys_eq!(account.key(), *address, SnailError::InvalidFreezeAccount);
+        targets.push(account.clone());
+    }
+    
+    // All of them in this transaction, before the authority is gone for good
+    for target in targets {
+        let already_frozen = TokenAccount::try_deserialize(&mut &target.try_borrow_data()?[..])?.is_frozen();
+        if already_frozen {
+            continue;
+        }
+        
+        token_interface::freeze_account(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                FreezeAccount {
+                    account: target,
+                    mint: accounts.snail_mint.to_account_info(),
+                    authority: accounts.freeze_authority.to_account_info(),
+                },
+                signer,
+            ),
+        )?;
+    }
+    
+    Ok(())
+}
+
+/// BurnTreasury outcome: burn everything in the outcome vault
+fn burn_treasury(accounts: &mut TouchSnail, signer: &[&[&[u8]]]) -> Result<u64> {
+    if accounts.game_state.outcome_mint == Pubkey::default() {
+        return Ok(0);
+    }
+    let (Some(outcome_vault), Some(outcome_mint), Some(outcome_token_program)) = (
+        &accounts.outcome_vault,
+        &accounts.outcome_mint,
+        &accounts.outcome_token_program,
+    ) else {
+        return err!(SnailError::MissingOutcomeAccounts);
+    };
+    
+    let amount = outcome::burn_treasury(&mut accounts.game_state, outcome_vault.amount)?;
+    if amount > 0 {
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                outcome_token_program.to_account_info(),
+                Burn {
+                    mint: outcome_mint.to_account_info(),
+                    from: outcome_vault.to_account_info(),
+                    authority: accounts.game_state.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+    }
+    
+    Ok(amount)
+}
+
+/// ReleaseRewards outcome: open `claim_reward` against the outcome vault
+fn release_rewards(accounts: &mut TouchSnail) -> Result<u64> {
+    if accounts.game_state.outcome_mint == Pubkey::default() {
+        return Ok(0);
+    }
+    let Some(outcome_vault) = &accounts.outcome_vault else {
+        return err!(SnailError::MissingOutcomeAccounts);
+    };
+    
+    outcome::release_rewards(
+        &mut accounts.game_state,
+        outcome_vault.amount,
+        accounts.snail_mint.supply,
+    )?;
+    
+    Ok(accounts.game_state.outcome_amount)
+}
+
+/// TransferPenalty outcome: send the outcome vault to the penalty recipient
+fn transfer_penalty(accounts: &mut TouchSnail, signer: &[&[&[u8]]]) -> Result<u64> {
+    if accounts.game_state.outcome_mint == Pubkey::default() {
+        return Ok(0);
+    }
+    let (Some(outcome_vault), Some(outcome_mint), Some(penalty_token_account), Some(outcome_token_program)) = (
+        &accounts.outcome_vault,
+        &accounts.outcome_mint,
+        &accounts.penalty_token_account,
+        &accounts.outcome_token_program,
+    ) else {
+        return err!(SnailError::MissingOutcomeAccounts);
+    };
+    
+    let amount = outcome::transfer_penalty(&mut accounts.game_state, outcome_vault.amount)?;
+    if amount > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                outcome_token_program.to_account_info(),
+                TransferChecked {
+                    from: outcome_vault.to_account_info(),
+                    mint: outcome_mint.to_account_info(),
+                    to: penalty_token_account.to_account_info(),
+                    authority: accounts.game_state.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+            outcome_mint.decimals,
+        )?;
+    }
+    
+    Ok(amount)
+}
+
+/// Spot market cap: SNAIL price times supply, in the game's quote unit
+/// (10^-market_cap_decimals USDC)
+/// The price is the average over the primary pool and every secondary pool in
+/// `game_state.pools`, weighted by the USDC each pool holds, so a thin pool barely
+/// moves it. The supply is the mint supply, minus the balances of the excluded
+/// accounts when the game uses circulating supply.
+/// `remaining_accounts` holds, in order: for each secondary pool its USDC vault,
+/// SNAIL vault and, when the price source reads one, pool state account; then, in
+/// circulating mode, every excluded account.
+/// Returns None when no pool has liquidity
+fn spot_market_cap(
+    game_state: &GameState,
+    pool: Option<&AccountInfo>,
+    usdc_lp: &InterfaceAccount<TokenAccount>,
+    snail_lp: &InterfaceAccount<TokenAccount>,
+    snail_mint: &InterfaceAccount<Mint>,
+    remaining_accounts: &[AccountInfo],
+) -> Result<Option<u128>> {
+    // (usdc vault, snail vault, pool state account) per secondary pool
+    let mut pools = Vec::with_capacity(game_state.pools.len());
+    let mut accounts = remaining_accounts.iter();
+    for config in &game_state.pools {
+        let mut next = || accounts.next().ok_or(SnailError::MissingPool);
+        let usdc_vault = read_vault(
+            next()?,
+            &config.usdc_vault,
+            &game_state.usdc_mint,
+            SnailError::InvalidPool,
+        )?;
+        let snail_vault = read_vault(
+            next()?,
+            &config.snail_vault,
+            &game_state.snail_mint,
+            SnailError::InvalidPool,
+        )?;
+        let pool = match config.price_source.program_id() {
+            Some(_) => {
+                let pool = next()?;
+                require_keys_eq!(pool.key(), config.pool, SnailError::InvalidPool);
+                Some(pool)
+            }
+            None => None,
+        };
+        pools.push((usdc_vault, snail_vault, pool));
+    }
+    
+    let supply = match game_state.supply_mode {
+        SupplyMode::FullyDiluted => snail_mint.supply,
+        SupplyMode::Circulating => {
+            let mut supply = snail_mint.supply;
+            for address in &game_state.excluded_accounts {
+                let account = accounts.next().ok_or(SnailError::MissingExcludedAccount)?;
+                let excluded = read_vault(
+                    account,
+                    address,
+                    &game_state.snail_mint,
+                    SnailError::InvalidExcludedAccount,
+                )?;
+                supply = supply.saturating_sub(excluded.amount);
+            }
+            supply
+        }
+    };
+    
+    // Borrow the pool state accounts for as long as the prices are read
+    let primary_data = pool.map(|pool| pool.try_borrow_data()).transpose()?;
+    let pool_data = pools
+        .iter()
+        .map(|(_, _, pool)| pool.map(|pool| pool.try_borrow_data()).transpose())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    
+    let mut snapshots = Vec::with_capacity(1 + pools.len());
+    snapshots.push(price::PoolSnapshot {
+        usdc_vault: vault(usdc_lp),
+        snail_vault: vault(snail_lp),
+        pool: pool.zip(primary_data.as_deref()).map(|(pool, data)| (*pool.owner, &data[..])),
+    });
+    snapshots.extend(pools.iter().zip(&pool_data).map(|((usdc_vault, snail_vault, pool), data)| {
+        price::PoolSnapshot {
+            usdc_vault: *usdc_vault,
+            snail_vault: *snail_vault,
+            pool: pool.zip(data.as_deref()).map(|(pool, data)| (*pool.owner, &data[..])),
+        }
+    }));
+    
+    pools_market_cap(game_state, &snapshots, supply)
+}
+
+/// Price from one pool, borrowing the pool state account if the source reads one
+fn pool_price(
+    price_source: PriceSource,
+    pool: Option<&AccountInfo>,
+    usdc_vault: price::Vault,
+    snail_vault: price::Vault,
+) -> Result<Option<price::Price>> {
+    let pool_data = pool.map(|pool| pool.try_borrow_data()).transpose()?;
+    let pool = pool.zip(pool_data.as_deref()).map(|(pool, data)| (pool.owner, &data[..]));
+    
+    price_source.price(pool, &usdc_vault, &snail_vault)
+}
+
+/// Token account passed in `remaining_accounts`, checked against its expected
+/// address and mint; `error` is raised when it does not match
+fn read_vault(
+    account: &AccountInfo,
+    address: &Pubkey,
+    mint: &Pubkey,
+    error: SnailError,
+) -> Result<price::Vault> {
+    require_keys_eq!(account.key(), *address, error);
+    if !TokenInterface::ids().contains(account.owner) {
+        return Err(error.into());
+    }
+    
+    let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    require_keys_eq!(token_account.mint, *mint, error);
+    
+    Ok(price::Vault {
+        address: account.key(),
+        mint: token_account.mint,
+        amount: token_account.amount,
+    })
+}
+
+fn vault(token_account: &InterfaceAccount<TokenAccount>) -> price::Vault {
+    price::Vault {
+        address: token_account.key(),
+        mint: token_account.mint,
+        amount: token_account.amount,
+    }
+}
+
+/// Whether `freeze_authority` is the freeze authority of `mint`
+/// An unarmed game can never be touched, whatever its market cap.
+fn is_armed(mint: &InterfaceAccount<Mint>, freeze_authority: &AccountInfo) -> bool {
+    Option::<Pubkey>::from(mint.freeze_authority) == Some(freeze_authority.key())
+}
+
+/// Whether transfers of `mint` invoke this program's transfer hook
+/// This program never transfers such a mint itself, since the transfer would
+/// re-enter snail_game through the hook. Deposits are plain transfers by the
+/// holder, and payouts approve the recipient as a delegate who pulls them.
+fn routes_through_this_program(mint: &InterfaceAccount<Mint>) -> Result<bool> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != token_2022::ID {
+        return Ok(false);
+    }
+    
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<anchor_spl::token_2022::spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint_state
+        .get_extension::<TransferHookExtension>()
+        .is_ok_and(|extension| Option::<Pubkey>::from(extension.program_id) == Some(crate::ID)))
+}
+
+/// Reject direct invocations of the hook outside of a Token-2022 transfer
+fn assert_is_transferring(source_token: &InterfaceAccount<TokenAccount>) -> Result<()> {
+    let source_token_info = source_token.to_account_info();
+    let data = source_token_info.try_borrow_data()?;
+    let account = StateWithExtensions::<anchor_spl::token_2022::spl_token_2022::state::Account>::unpack(&data)?;
+    let extension = account.get_extension::<TransferHookAccount>()?;
+    
+    require!(bool::from(extension.transferring), SnailError::NotTransferring);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + GameState::LEN,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ObservationBuffer::LEN,
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// USDC LP token account
+    #[account(constraint = usdc_lp.mint == usdc_mint.key() @ SnailError::InvalidUsdcLpMint)]
+    pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL LP token account
+    #[account(
+        constraint = snail_lp.key() != usdc_lp.key() @ SnailError::DuplicateLpAccounts,
+        constraint = snail_lp.mint == snail_mint.key() @ SnailError::InvalidSnailLpMint
+    )]
+    pub snail_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL mint account (its freeze authority must be the freeze authority PDA)
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Freeze authority PDA of this game
+    #[account(
+        seeds = [b"freeze-authority", game_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub freeze_authority: AccountInfo<'info>,
+    
+    /// CHECK: Pool state account, required unless the price source is TokenAccounts;
+    /// validated against the price source in the handler
+    pub pool: Option<UncheckedAccount<'info>>,
+    
+    /// USDC mint account (quote token)
+    #[account(
+        constraint = usdc_mint.key() != snail_mint.key() @ SnailError::InvalidUsdcMint,
+        constraint = usdc_mint.decimals <= MAX_MARKET_CAP_DECIMALS @ SnailError::InvalidUsdcMint
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PreviewGame {}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct CheckRequiredMarketCap<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct CheckCurrentMarketCap<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_lp @ SnailError::InvalidUsdcLp,
+        has_one = snail_lp @ SnailError::InvalidSnailLp,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    /// USDC LP token account (must be the one recorded in GameState)
+    #[account(constraint = usdc_lp.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
+    pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL LP token account (must be the one recorded in GameState)
+    #[account(constraint = snail_lp.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint)]
+    pub snail_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL mint account
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct TouchSnail<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_lp @ SnailError::InvalidUsdcLp,
+        has_one = snail_lp @ SnailError::InvalidSnailLp,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// USDC LP token account (must be the one recorded in GameState)
+    #[account(constraint = usdc_lp.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
+    pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL LP token account (will be frozen)
+    #[account(
+        mut,
+        constraint = snail_lp.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint
+    )]
+    pub snail_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL mint account
+    #[account(mut)]
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Freeze authority PDA
+    #[account(
+        seeds = [b"freeze-authority", game_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub freeze_authority: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    
+    pub toucher: Signer<'info>,
+    
+    /// CHECK: Caller of the pending touch, required by `confirm_touch`
+    #[account(address = game_state.toucher @ SnailError::MissingPendingToucher)]
+    pub pending_toucher: Option<UncheckedAccount<'info>>,
+    
+    /// Bounty vault, required when the game has a bounty
+    #[account(
+        mut,
+        seeds = [b"bounty_vault", game_state.key().as_ref()],
+        bump
+    )]
+    pub bounty_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = game_state.bounty_mint @ SnailError::InvalidBountyMint)]
+    pub bounty_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// Toucher's token account receiving the bounty, checked against the toucher
+    /// paid in `settle_touch`
+    #[account(
+        mut,
+        token::mint = game_state.bounty_mint
+    )]
+    pub toucher_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Token program of the bounty mint (Token or Token-2022)
+    pub bounty_token_program: Option<Interface<'info, TokenInterface>>,
+    
+    /// Outcome vault, required when the game has one
+    #[account(
+        mut,
+        seeds = [b"outcome_vault", game_state.key().as_ref()],
+        bump
+    )]
+    pub outcome_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    
+    #[account(mut, address = game_state.outcome_mint @ SnailError::InvalidOutcomeMint)]
+    pub outcome_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    
+    /// Penalty recipient's token account, for TransferPenalty
+    #[account(
+        mut,
+        constraint = penalty_token_account.mint == game_state.outcome_mint @ SnailError::InvalidPenaltyAccount,
+        constraint = penalty_token_account.owner == game_state.outcome.penalty_recipient() @ SnailError::InvalidPenaltyAccount
+    )]
+    pub penalty_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    
+    /// Token program of the outcome mint (Token or Token-2022)
+    pub outcome_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct ClearTouch<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_lp @ SnailError::InvalidUsdcLp,
+        has_one = snail_lp @ SnailError::InvalidSnailLp,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// USDC LP token account (must be the one recorded in GameState)
+    #[account(constraint = usdc_lp.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
+    pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL LP token account (must be the one recorded in GameState)
+    #[account(constraint = snail_lp.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint)]
+    pub snail_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL mint account
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct DeclareVictory<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    /// SNAIL mint account (freeze authority renounced on request)
+    #[account(mut)]
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Freeze authority PDA
+    #[account(
+        seeds = [b"freeze-authority", game_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub freeze_authority: AccountInfo<'info>,
+    
+    pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeOutcomeVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Outcome mint (SNAIL treasury, USDC rewards, ...)
+    pub outcome_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Outcome vault, owned by the game_state PDA
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"outcome_vault", game_state.key().as_ref()],
+        bump,
+        token::mint = outcome_mint,
+        token::authority = game_state,
+        token::token_program = token_program
+    )]
+    pub outcome_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct ClaimReward<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = snail_mint @ SnailError::InvalidSnailMint,
+        has_one = outcome_mint @ SnailError::InvalidOutcomeMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(mut)]
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Holder's SNAIL, burned for the reward
+    #[account(
+        mut,
+        token::mint = snail_mint,
+        token::authority = holder
+    )]
+    pub holder_snail_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"outcome_vault", game_state.key().as_ref()],
+        bump
+    )]
+    pub outcome_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub outcome_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Holder's token account receiving the reward
+    #[account(mut, token::mint = outcome_mint)]
+    pub holder_reward_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub holder: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    
+    /// Token program of the outcome mint (Token or Token-2022)
+    pub outcome_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeShell<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Shell::LEN,
+        seeds = [b"shell", game_state.key().as_ref()],
+        bump
+    )]
+    pub shell: Account<'info, Shell>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Reward mint (USDC, ...)
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Reward vault, owned by the game_state PDA
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"shell_reward_vault", game_state.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = game_state,
+        token::token_program = token_program
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct OpenStake<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        seeds = [b"shell", game_state.key().as_ref()],
+        bump
+    )]
+    pub shell: Account<'info, Shell>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Stake::LEN,
+        seeds = [b"stake", game_state.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, Stake>,
+    
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// SNAIL stake vault, owned by the stake PDA
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"stake_vault", stake.key().as_ref()],
+        bump,
+        token::mint = snail_mint,
+        token::authority = stake,
+        token::token_program = token_program
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct StakeSnail<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"shell", game_state.key().as_ref()],
+        bump
+    )]
+    pub shell: Account<'info, Shell>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", game_state.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub stake: Account<'info, Stake>,
+    
+    #[account(
+        seeds = [b"stake_vault", stake.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct Unstake<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"shell", game_state.key().as_ref()],
+        bump
+    )]
+    pub shell: Account<'info, Shell>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", game_state.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub stake: Account<'info, Stake>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake_vault", stake.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct ClaimShellReward<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"shell", game_state.key().as_ref()],
+        bump,
+        has_one = reward_mint @ SnailError::InvalidRewardMint
+    )]
+    pub shell: Account<'info, Shell>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", game_state.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub stake: Account<'info, Stake>,
+    
+    #[account(
+        mut,
+        seeds = [b"shell_reward_vault", game_state.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Owner's token account receiving the reward
+    #[account(mut, token::mint = reward_mint)]
+    pub owner_reward_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    /// Token program of the reward mint (Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct SweepShellRewards<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"shell", game_state.key().as_ref()],
+        bump,
+        has_one = reward_mint @ SnailError::InvalidRewardMint
+    )]
+    pub shell: Account<'info, Shell>,
+    
+    #[account(
+        mut,
+        seeds = [b"shell_reward_vault", game_state.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Game owner's token account receiving the rewards
+    #[account(mut, token::mint = reward_mint)]
+    pub owner_reward_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    /// Token program of the reward mint (Token or Token-2022)
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct SlashStake<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"shell", game_state.key().as_ref()],
+        bump
+    )]
+    pub shell: Account<'info, Shell>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", game_state.key().as_ref(), stake.owner.as_ref()],
+        bump
+    )]
+    pub stake: Account<'info, Stake>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake_vault", stake.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Toucher recorded by touch_snail, approved as the vault's delegate
+    #[account(address = game_state.toucher @ SnailError::Unauthorized)]
+    pub toucher: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeMarket<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized,
+        has_one = usdc_mint @ SnailError::InvalidUsdcMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Market::LEN,
+        seeds = [b"market", game_state.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    /// USDC deposits of both sides, owned by the game_state PDA
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"market_vault", game_state.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = game_state,
+        token::token_program = token_program
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct OpenPosition<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        seeds = [b"market", game_state.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::LEN,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct DepositPrediction<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_mint @ SnailError::InvalidUsdcMint,
+        has_one = usdc_lp @ SnailError::InvalidUsdcLp,
+        has_one = snail_lp @ SnailError::InvalidSnailLp,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// USDC LP token account (must be the one recorded in GameState)
+    #[account(constraint = usdc_lp.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
+    pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL LP token account (must be the one recorded in GameState)
+    #[account(constraint = snail_lp.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint)]
+    pub snail_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL mint account
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [b"market", game_state.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(
+        mut,
+        seeds = [b"market_vault", game_state.key().as_ref()],
+        bump
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = owner
+    )]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct ClaimPrediction<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_mint @ SnailError::InvalidUsdcMint
     )]
     pub game_state: Account<'info, GameState>,
     
+    #[account(
+        seeds = [b"market", game_state.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", market.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub position: Account<'info, Position>,
+    
+    #[account(
+        mut,
+        seeds = [b"market_vault", game_state.key().as_ref()],
+        bump
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, token::mint = usdc_mint)]
+    pub owner_usdc_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub owner: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64, next_game_id: u64)]
+pub struct StartNextSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub previous_game_state: Box<Account<'info, GameState>>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + GameState::LEN,
+        seeds = [b"game_state", next_game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Box<Account<'info, GameState>>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ObservationBuffer::LEN,
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// SNAIL mint account (freeze authority moved to the new game)
+    #[account(mut)]
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Freeze authority PDA of the previous game
+    #[account(
+        seeds = [b"freeze-authority", game_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub previous_freeze_authority: AccountInfo<'info>,
+    
+    /// CHECK: Freeze authority PDA of the new game
+    #[account(
+        seeds = [b"freeze-authority", next_game_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub freeze_authority: AccountInfo<'info>,
+    
+    /// CHECK: ExtraAccountMetaList PDA of the mint, rewritten when it exists
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", snail_mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
     #[account(mut)]
     pub owner: Signer<'info>,
     
+    pub token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CheckRequiredMarketCap<'info> {
+#[instruction(game_id: u64)]
+pub struct AddPool<'info> {
     #[account(
-        seeds = [b"game_state"],
-        bump
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
     )]
     pub game_state: Account<'info, GameState>,
+    
+    pub owner: Signer<'info>,
+    
+    /// Pool's USDC vault
+    #[account(constraint = usdc_vault.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Pool's SNAIL vault
+    #[account(constraint = snail_vault.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint)]
+    pub snail_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Pool state account, listing both vaults; validated against the price
+    /// source in the handler
+    pub pool: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: ExtraAccountMetaList PDA, must not exist yet
+    #[account(
+        seeds = [b"extra-account-metas", game_state.snail_mint.as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-pub struct CheckCurrentMarketCap<'info> {
+#[instruction(game_id: u64)]
+pub struct ConfigureSupply<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    pub owner: Signer<'info>,
+    
+    /// CHECK: ExtraAccountMetaList PDA, must not exist yet
     #[account(
-        seeds = [b"game_state"],
+        seeds = [b"extra-account-metas", game_state.snail_mint.as_ref()],
         bump
     )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct ConfigureFreezeAccounts<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeBounty<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Bounty mint (SNAIL, USDC, ...)
+    pub bounty_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Bounty vault, owned by the game_state PDA
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"bounty_vault", game_state.key().as_ref()],
+        bump,
+        token::mint = bounty_mint,
+        token::authority = game_state,
+        token::token_program = token_program
+    )]
+    pub bounty_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct RecordObservation<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_lp @ SnailError::InvalidUsdcLp,
+        has_one = snail_lp @ SnailError::InvalidSnailLp,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
     pub game_state: Account<'info, GameState>,
     
-    /// CHECK: USDC LP token account
+    #[account(
+        mut,
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// USDC LP token account (must be the one recorded in GameState)
+    #[account(constraint = usdc_lp.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
     pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
     
-    /// CHECK: SNAIL LP token account
+    /// SNAIL LP token account (must be the one recorded in GameState)
+    #[account(constraint = snail_lp.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint)]
     pub snail_lp: InterfaceAccount<'info, TokenAccount>,
     
-    /// CHECK: SNAIL mint account
+    /// SNAIL mint account
     pub snail_mint: InterfaceAccount<'info, Mint>,
     
-    pub token_program: Program<'info, Token2022>,
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
 }
 
 #[derive(Accounts)]
-pub struct TouchSnail<'info> {
+#[instruction(game_id: u64)]
+pub struct InitializeCheckpointLog<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CheckpointLog::LEN,
+        seeds = [b"checkpoints", game_state.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: Box<Account<'info, CheckpointLog>>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct RecordCheckpoint<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_lp @ SnailError::InvalidUsdcLp,
+        has_one = snail_lp @ SnailError::InvalidSnailLp,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
     #[account(
         mut,
-        seeds = [b"game_state"],
+        seeds = [b"checkpoints", game_state.key().as_ref()],
+        bump
+    )]
+    pub checkpoints: Box<Account<'info, CheckpointLog>>,
+    
+    #[account(
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// USDC LP token account (must be the one recorded in GameState)
+    #[account(constraint = usdc_lp.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
+    pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL LP token account (must be the one recorded in GameState)
+    #[account(constraint = snail_lp.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint)]
+    pub snail_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL mint account
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct GetCheckpoints<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        seeds = [b"checkpoints", game_state.key().as_ref()],
         bump
     )]
+    pub checkpoints: Box<Account<'info, CheckpointLog>>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct GetGameStatus<'info> {
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = usdc_lp @ SnailError::InvalidUsdcLp,
+        has_one = snail_lp @ SnailError::InvalidSnailLp,
+        has_one = snail_mint @ SnailError::InvalidSnailMint
+    )]
     pub game_state: Account<'info, GameState>,
     
-    /// CHECK: USDC LP token account
+    #[account(
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// USDC LP token account (must be the one recorded in GameState)
+    #[account(constraint = usdc_lp.mint == game_state.usdc_mint @ SnailError::InvalidUsdcLpMint)]
     pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
     
-    /// CHECK: SNAIL LP token account (will be frozen)
-    #[account(mut)]
+    /// SNAIL LP token account (must be the one recorded in GameState)
+    #[account(constraint = snail_lp.mint == game_state.snail_mint @ SnailError::InvalidSnailLpMint)]
     pub snail_lp: InterfaceAccount<'info, TokenAccount>,
     
-    /// CHECK: SNAIL mint account
-    #[account(mut)]
+    /// SNAIL mint account
     pub snail_mint: InterfaceAccount<'info, Mint>,
     
-    /// CHECK: Freeze authority PDA (will be renounced)
+    /// CHECK: Freeze authority PDA, compared with the mint's freeze authority
     #[account(
-        seeds = [b"freeze-authority"],
+        seeds = [b"freeze-authority", game_id.to_le_bytes().as_ref()],
         bump,
     )]
     pub freeze_authority: AccountInfo<'info>,
     
-    pub token_program: Program<'info, Token2022>,
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: ExtraAccountMetaList PDA, written by the program
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(TransferHook::extra_account_metas(&game_state)?.len())?,
+        seeds = [b"extra-account-metas", snail_mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"game_state", game_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = game_state.is_configured() @ SnailError::NotConfigured,
+        has_one = snail_mint @ SnailError::InvalidSnailMint,
+        has_one = owner @ SnailError::Unauthorized
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    pub owner: Signer<'info>,
+    
+    /// SNAIL mint account (must point its transfer hook at this program)
+    pub snail_mint: InterfaceAccount<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Account order is fixed by the transfer hook interface: the first five accounts
+/// come from Token-2022, the rest are resolved from the ExtraAccountMetaList
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Source token account owner, can be a PDA or a delegate
+    pub owner: UncheckedAccount<'info>,
+    
+    /// CHECK: ExtraAccountMetaList PDA
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.game_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = game_state.snail_mint == mint.key() @ SnailError::InvalidSnailMint
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    /// USDC LP token account
+    #[account(address = game_state.usdc_lp @ SnailError::InvalidUsdcLp)]
+    pub usdc_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    /// SNAIL LP token account
+    #[account(address = game_state.snail_lp @ SnailError::InvalidSnailLp)]
+    pub snail_lp: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", game_state.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, ObservationBuffer>>,
+    
+    /// CHECK: Pool state account, for price sources that read one
+    #[account(address = game_state.pool @ SnailError::InvalidPool)]
+    pub pool: Option<UncheckedAccount<'info>>,
+}
+
+impl TransferHook<'_> {
+    /// Extra accounts Token-2022 appends to every `execute` call:
+    /// game_state (writable, to record breaches), usdc_lp, snail_lp, observations,
+    /// the primary pool state account (this program's id when there is none), then
+    /// every secondary pool and excluded account as laid out for `spot_market_cap`
+    pub fn extra_account_metas(game_state: &GameState) -> Result<Vec<ExtraAccountMeta>> {
+        let mut extra_account_metas = vec![
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal { bytes: b"game_state".to_vec() },
+                    Seed::Literal { bytes: game_state.game_id.to_le_bytes().to_vec() },
+                ],
+                false,
+                true,
+            )?,
+            ExtraAccountMeta::new_with_pubkey(&game_state.usdc_lp, false, false)?,
+            ExtraAccountMeta::new_with_pubkey(&game_state.snail_lp, false, false)?,
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal { bytes: b"observations".to_vec() },
+                    Seed::AccountKey { index: 5 }, // game_state
+                ],
+                false,
+                true,
+            )?,
+        ];
+        
+        // Anchor reads this program's id as an absent optional account
+        let pool = match game_state.price_source {
+            PriceSource::TokenAccounts => crate::ID,
+            _ => game_state.pool,
+        };
+        extra_account_metas.push(ExtraAccountMeta::new_with_pubkey(&pool, false, false)?);
+        
+        for config in &game_state.pools {
+            extra_account_metas.push(ExtraAccountMeta::new_with_pubkey(&config.usdc_vault, false, false)?);
+            extra_account_metas.push(ExtraAccountMeta::new_with_pubkey(&config.snail_vault, false, false)?);
+            if config.price_source != PriceSource::TokenAccounts {
+                extra_account_metas.push(ExtraAccountMeta::new_with_pubkey(&config.pool, false, false)?);
+            }
+        }
+        
+        if game_state.supply_mode == SupplyMode::Circulating {
+            for address in &game_state.excluded_accounts {
+                extra_account_metas.push(ExtraAccountMeta::new_with_pubkey(address, false, false)?);
+            }
+        }
+        
+        Ok(extra_account_metas)
+    }
 }
 
-
 #[account]
+#[derive(Default)]
 pub struct GameState {
     pub owner: Pubkey,
-    pub snail_start_stamp: i64,
+    pub game_id: u64,
+    pub snail_start_stamp: i64, // Unix timestamp or slot, per timing_mode
     pub snail_end_stamp: i64,
-    pub target_market_cap: u64,
-    pub curve_factor: u64, // Stored with 1 decimal precision (77 = 7.7)
+    pub target_market_cap: u64, // In units of 10^-market_cap_decimals USDC
+    pub curve: CurveKind,
     pub usdc_lp: Pubkey,
     pub snail_lp: Pubkey,
     pub snail_mint: Pubkey,
-    pub configured: bool,
-    pub frozen: bool,
+    pub usdc_mint: Pubkey,
+    pub price_source: PriceSource,
+    pub pool: Pubkey, // Pool state account, Pubkey::default() for TokenAccounts
+    pub pools: Vec<PoolConfig>, // Secondary pools, up to MAX_POOLS
+    pub supply_mode: SupplyMode,
+    pub excluded_accounts: Vec<Pubkey>, // Subtracted from supply in circulating mode
+    pub freeze_accounts: Vec<Pubkey>, // Frozen by touch_snail besides the pools
+    pub snail_decimals: u8,
+    pub usdc_decimals: u8,
+    pub market_cap_decimals: u8, // Quote unit of every market cap: 0 = whole USDC
+    pub twap_window: i64, // Seconds averaged by touch_snail, 0 = spot price
+    pub grace_period: i64, // Seconds between touch_snail and confirm_touch, 0 = freeze at once
+    pub touched_at: i64, // Time of the pending touch
+    pub touch_time_point: i64, // Same, per timing_mode: where confirm_touch reads the curve
+    pub phase: GamePhase,
+    pub breached_at: i64,
+    pub breach_market_cap: u64,
+    pub breach_required_market_cap: u64,
+    pub bounty_mint: Pubkey, // Pubkey::default() when the game has no bounty
+    pub bounty_amount: u64, // Fixed payout, or
+    pub bounty_bps: u16, // share of the vault balance paid out
+    pub outcome: Outcome,
+    pub outcome_mint: Pubkey, // Pubkey::default() until the outcome vault exists
+    pub outcome_amount: u64, // Burned, released or transferred by the touch
+    pub reward_supply: u64, // SNAIL supply released rewards are shared over
+    pub toucher: Pubkey, // Caller of the touch, pending or settled; paid the bounty and slashed stakes
+    pub survived_at: i64,
+    pub season: u32, // 0 for a game created by initialize
+    pub previous_game_id: Option<u64>, // Game this season followed
+    pub next_game_id: Option<u64>, // Season started from this game, at most one
+    pub survived_seasons: u32, // Earlier seasons that survived
+    pub timing_mode: TimingMode,
 }
 
 impl GameState {
     pub const LEN: usize = 8 + // discriminator
         32 + // owner
+        8 + // game_id
         8 + // snail_start_stamp
         8 + // snail_end_stamp
         8 + // target_market_cap
-        8 + // curve_factor
+        CurveKind::LEN + // curve
         32 + // usdc_lp
         32 + // snail_lp
         32 + // snail_mint
-        1 + // configured
-        1; // frozen
+        32 + // usdc_mint
+        1 + // price_source
+        32 + // pool
+        4 + MAX_POOLS * PoolConfig::LEN + // pools
+        1 + // supply_mode
+        4 + MAX_EXCLUDED_ACCOUNTS * 32 + // excluded_accounts
+        4 + MAX_FREEZE_ACCOUNTS * 32 + // freeze_accounts
+        1 + // snail_decimals
+        1 + // usdc_decimals
+        1 + // market_cap_decimals
+        8 + // twap_window
+        8 + // grace_period
+        8 + // touched_at
+        8 + // touch_time_point
+        1 + // phase
+        8 + // breached_at
+        8 + // breach_market_cap
+        8 + // breach_required_market_cap
+        32 + // bounty_mint
+        8 + // bounty_amount
+        2 + // bounty_bps
+        Outcome::LEN + // outcome
+        32 + // outcome_mint
+        8 + // outcome_amount
+        8 + // reward_supply
+        32 + // toucher
+        8 + // survived_at
+        4 + // season
+        1 + 8 + // previous_game_id
+        1 + 8 + // next_game_id
+        4 + // survived_seasons
+        1; // timing_mode
+    
+    pub fn is_configured(&self) -> bool {
+        self.phase != GamePhase::Uninitialized
+    }
+    
+    /// Where `clock` is in the game window's unit: unix timestamp or slot
+    pub fn time_point(&self, clock: &Clock) -> i64 {
+        self.timing_mode.time_point(clock)
+    }
+    
+    /// Phase at `time_point`, including the time-driven Pending -> Live move
+    /// that has not been written to the account yet
+    pub fn phase_at(&self, time_point: i64) -> GamePhase {
+        match self.phase {
+            GamePhase::Pending if time_point >= self.snail_start_stamp => GamePhase::Live,
+            phase => phase,
+        }
+    }
+    
+    /// Move to `phase`, emitting `GamePhaseChanged` if it differs
+    pub fn set_phase(&mut self, phase: GamePhase, timestamp: i64) {
+        if self.phase == phase {
+            return;
+        }
+        
+        emit!(GamePhaseChanged {
+            game_id: self.game_id,
+            previous_phase: self.phase,
+            phase,
+            timestamp,
+        });
+        self.phase = phase;
+    }
+    
+    /// Write any time-driven phase change
+    pub fn sync_phase(&mut self, clock: &Clock) {
+        self.set_phase(self.phase_at(self.time_point(clock)), clock.unix_timestamp);
+    }
+    
+    pub fn has_bounty(&self) -> bool {
+        self.bounty_mint != Pubkey::default()
+    }
+    
+    /// Bounty paid out of a vault holding `vault_balance`
+    pub fn bounty_payout(&self, vault_balance: u64) -> u64 {
+        if self.bounty_bps > 0 {
+            ((vault_balance as u128) * (self.bounty_bps as u128) / (BPS as u128)) as u64
+        } else {
+            self.bounty_amount.min(vault_balance)
+        }
+    }
+    
+    /// Whether `token_account` is the SNAIL vault of one of the game's pools
+    pub fn is_pool_snail_vault(&self, token_account: &Pubkey) -> bool {
+        *token_account == self.snail_lp || self.pools.iter().any(|pool| pool.snail_vault == *token_account)
+    }
+    
+    /// Number of remaining accounts read by `spot_market_cap`
+    pub fn market_cap_account_count(&self) -> usize {
+        let pools: usize = self
+            .pools
+            .iter()
+            .map(|pool| 2 + pool.price_source.program_id().is_some() as usize)
+            .sum();
+        let excluded = match self.supply_mode {
+            SupplyMode::FullyDiluted => 0,
+            SupplyMode::Circulating => self.excluded_accounts.len(),
+        };
+        pools + excluded
+    }
+    
+    /// Minimum spacing between stored observations, chosen so the ring buffer
+    /// always reaches back at least two TWAP windows
+    pub fn observation_interval(&self) -> i64 {
+        (self.twap_window / (MAX_OBSERVATIONS as i64 / 2)).max(1)
+    }
+    
+    /// The next season of this finished game, checked like `initialize`: the same
+    /// configuration with the target scaled by `target_multiplier_bps`
+    pub fn next_season(
+        &self,
+        next_game_id: u64,
+        target_multiplier_bps: u32,
+        snail_start_stamp: i64,
+        duration: i64,
+        time_point: i64,
+    ) -> Result<GameState> {
+        require!(
+            matches!(self.phase, GamePhase::Touched | GamePhase::Survived),
+            SnailError::GameNotOver
+        );
+        require!(self.next_game_id.is_none(), SnailError::SeasonAlreadyStarted);
+        let snail_end_stamp = snail_start_stamp
+            .checked_add(duration)
+            .ok_or(SnailError::InvalidTimestamps)?;
+        let target_market_cap = u64::try_from(
+            self.target_market_cap as u128 * target_multiplier_bps as u128 / BPS as u128,
+        )
+        .map_err(|_| SnailError::MathOverflow)?;
+        validate_game_parameters(
+            snail_start_stamp,
+            snail_end_stamp,
+            target_market_cap,
+            self.market_cap_decimals,
+            &self.curve,
+            self.twap_window,
+            self.grace_period,
+            &self.outcome,
+            time_point,
+        )?;
+        
+        Ok(GameState {
+            owner: self.owner,
+            game_id: next_game_id,
+            snail_start_stamp,
+            snail_end_stamp,
+            target_market_cap,
+            curve: self.curve.clone(),
+            usdc_lp: self.usdc_lp,
+            snail_lp: self.snail_lp,
+            snail_mint: self.snail_mint,
+            usdc_mint: self.usdc_mint,
+            price_source: self.price_source,
+            pool: self.pool,
+            pools: self.pools.clone(),
+            supply_mode: self.supply_mode,
+            excluded_accounts: self.excluded_accounts.clone(),
+            freeze_accounts: self.freeze_accounts.clone(),
+            snail_decimals: self.snail_decimals,
+            usdc_decimals: self.usdc_decimals,
+            market_cap_decimals: self.market_cap_decimals,
+            twap_window: self.twap_window,
+            grace_period: self.grace_period,
+            outcome: self.outcome,
+            timing_mode: self.timing_mode,
+            season: self.season + 1,
+            previous_game_id: Some(self.game_id),
+            survived_seasons: self.survived_seasons + (self.phase == GamePhase::Survived) as u32,
+            ..Default::default()
+        })
+    }
+}
+
+/// Secondary pools a game can aggregate besides the primary one
+pub const MAX_POOLS: usize = 4;
+
+/// A secondary SNAIL/USDC pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolConfig {
+    pub price_source: PriceSource,
+    pub pool: Pubkey, // Pool state account, Pubkey::default() for TokenAccounts
+    pub usdc_vault: Pubkey,
+    pub snail_vault: Pubkey,
+}
+
+impl PoolConfig {
+    pub const LEN: usize = 1 + // price_source
+        32 + // pool
+        32 + // usdc_vault
+        32; // snail_vault
+}
+
+/// Token accounts a game can exclude from circulating supply
+pub const MAX_EXCLUDED_ACCOUNTS: usize = 8;
+
+/// Extra token accounts a game can freeze on touch
+pub const MAX_FREEZE_ACCOUNTS: usize = 8;
+
+/// Supply the market cap is computed from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SupplyMode {
+    #[default]
+    FullyDiluted, // Mint supply
+    Circulating, // Mint supply minus the excluded accounts
+}
+
+/// Clock the game window is measured in, chosen at `initialize`
+/// TWAP windows, grace periods and event timestamps stay in unix seconds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimingMode {
+    #[default]
+    UnixTimestamp, // snail_start_stamp and snail_end_stamp are unix timestamps
+    Slot, // They are slots, and curve progress follows Clock::slot
+}
+
+impl TimingMode {
+    pub fn time_point(&self, clock: &Clock) -> i64 {
+        match self {
+            TimingMode::UnixTimestamp => clock.unix_timestamp,
+            TimingMode::Slot => clock.slot as i64,
+        }
+    }
+}
+
+/// Lifecycle of a game
+/// Pending -> Live happens with time and is written by the next instruction that
+/// touches the game; every other move is made by an instruction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GamePhase {
+    #[default]
+    Uninitialized,
+    Pending, // Configured, before snail_start_stamp
+    Live,
+    Breached, // Seen under the curve by the transfer hook, waiting for touch_snail
+    Touched, // Pool frozen
+    Survived, // Declared after snail_end_stamp
+    TouchPending, // Touched with a grace period, waiting for confirm_touch or clear_touch
+}
+
+/// Returned by `get_game_status`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct GameStatus {
+    pub phase: GamePhase,
+    pub current_market_cap: u64,
+    pub required_market_cap: u64,
+    pub margin: i128, // current - required, negative when the snail can be touched
+    pub time_remaining: i64, // Until snail_end_stamp: seconds for UnixTimestamp, slots for Slot
+    pub armed: bool, // False when the freeze authority PDA is not the mint's freeze authority
+}
+
+/// Points returned by preview_game
+pub const PREVIEW_SAMPLES: usize = 32;
+
+/// One sample of the required market cap curve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct CurvePoint {
+    pub time_point: i64,
+    pub required_market_cap: u64,
+}
+
+/// Finest quote unit a game can use for market caps (1e-18 USDC)
+pub const MAX_MARKET_CAP_DECIMALS: u8 = 18;
+
+pub const MAX_OBSERVATIONS: usize = 64;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub market_cap: u64, // Spot market cap seen at `timestamp`
+    pub cumulative_market_cap: u128, // Sum of market_cap * seconds up to `timestamp`
+}
+
+impl Observation {
+    pub const LEN: usize = 8 + // timestamp
+        8 + // market_cap
+        16; // cumulative_market_cap
+}
+
+pub const MAX_CHECKPOINTS: usize = 128;
+
+/// Checkpoints returned by get_checkpoints, bounded by the 1024-byte return data
+pub const MAX_CHECKPOINTS_RETURNED: usize = 40;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct Checkpoint {
+    pub timestamp: i64,
+    pub current_market_cap: u64,
+    pub required_market_cap: u64,
+}
+
+impl Checkpoint {
+    pub const LEN: usize = 8 + // timestamp
+        8 + // current_market_cap
+        8; // required_market_cap
+}
+
+/// Ring buffer of market cap checkpoints for one game, for charting
+#[account]
+pub struct CheckpointLog {
+    pub game_state: Pubkey,
+    pub head: u16, // Index of the most recent checkpoint
+    pub count: u16,
+    pub last_slot: u64, // Slot of the most recent checkpoint
+    pub checkpoints: [Checkpoint; MAX_CHECKPOINTS],
+}
+
+impl CheckpointLog {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // game_state
+        2 + // head
+        2 + // count
+        8 + // last_slot
+        MAX_CHECKPOINTS * Checkpoint::LEN; // checkpoints
+    
+    /// Append `checkpoint`, overwriting the oldest once full
+    pub fn record(&mut self, slot: u64, checkpoint: Checkpoint) -> Result<()> {
+        require!(
+            self.count == 0 || slot > self.last_slot,
+            SnailError::CheckpointTooSoon
+        );
+        
+        if self.count > 0 {
+            self.head = ((self.head as usize + 1) % MAX_CHECKPOINTS) as u16;
+        }
+        self.count = (self.count + 1).min(MAX_CHECKPOINTS as u16);
+        self.last_slot = slot;
+        self.checkpoints[self.head as usize] = checkpoint;
+        
+        Ok(())
+    }
+    
+    /// Up to `count` most recent checkpoints, oldest first
+    pub fn latest(&self, count: usize) -> Vec<Checkpoint> {
+        let count = count.min(self.count as usize).min(MAX_CHECKPOINTS_RETURNED);
+        (0..count)
+            .rev()
+            .map(|i| self.checkpoints[(self.head as usize + MAX_CHECKPOINTS - i) % MAX_CHECKPOINTS])
+            .collect()
+    }
+}
+
+/// Ring buffer of market cap observations for one game
+#[account]
+pub struct ObservationBuffer {
+    pub game_state: Pubkey,
+    pub head: u16, // Index of the most recent observation
+    pub count: u16,
+    pub observations: [Observation; MAX_OBSERVATIONS],
+}
+
+impl ObservationBuffer {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // game_state
+        2 + // head
+        2 + // count
+        MAX_OBSERVATIONS * Observation::LEN; // observations
+    
+    pub fn latest(&self) -> Option<&Observation> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(&self.observations[self.head as usize])
+    }
+    
+    /// Record the spot market cap at `timestamp`
+    /// The price seen at the previous observation is what accumulates over the elapsed
+    /// time, so a price pushed within the current block carries no weight yet.
+    /// Observations within the same `interval` bucket overwrite the latest slot.
+    pub fn record(&mut self, timestamp: i64, market_cap: u128, interval: i64) -> Result<()> {
+        let market_cap = u64::try_from(market_cap).unwrap_or(u64::MAX);
+        
+        let Some(latest) = self.latest().copied() else {
+            self.head = 0;
+            self.count = 1;
+            self.observations[0] = Observation {
+                timestamp,
+                market_cap,
+                cumulative_market_cap: 0,
+            };
+            return Ok(());
+        };
+        
+        require!(timestamp >= latest.timestamp, SnailError::InvalidTimestamps);
+        
+        let elapsed = (timestamp - latest.timestamp) as u128;
+        let cumulative_market_cap = latest
+            .cumulative_market_cap
+            .checked_add((latest.market_cap as u128) * elapsed)
+            .ok_or(SnailError::MathOverflow)?;
+        
+        if timestamp.div_euclid(interval) != latest.timestamp.div_euclid(interval) {
+            self.head = ((self.head as usize + 1) % MAX_OBSERVATIONS) as u16;
+            self.count = (self.count + 1).min(MAX_OBSERVATIONS as u16);
+        }
+        
+        self.observations[self.head as usize] = Observation {
+            timestamp,
+            market_cap,
+            cumulative_market_cap,
+        };
+        
+        Ok(())
+    }
+    
+    /// Time-weighted average market cap over at least `window` seconds ending at `timestamp`
+    pub fn twap(&self, timestamp: i64, window: i64) -> Result<u128> {
+        let latest = self.latest().ok_or(SnailError::InsufficientObservations)?;
+        require!(timestamp >= latest.timestamp, SnailError::InvalidTimestamps);
+        
+        let cumulative_now = latest
+            .cumulative_market_cap
+            .checked_add((latest.market_cap as u128) * ((timestamp - latest.timestamp) as u128))
+            .ok_or(SnailError::MathOverflow)?;
+        
+        // Newest observation at or before the start of the window
+        let window_start = timestamp - window;
+        let start = (0..self.count as usize)
+            .map(|i| &self.observations[(self.head as usize + MAX_OBSERVATIONS - i) % MAX_OBSERVATIONS])
+            .find(|observation| observation.timestamp <= window_start)
+            .ok_or(SnailError::InsufficientObservations)?;
+        
+        let elapsed = (timestamp - start.timestamp) as u128;
+        Ok((cumulative_now - start.cumulative_market_cap) / elapsed)
+    }
 }
 
 #[error_code]
@@ -358,10 +3721,485 @@ pub enum SnailError {
     InvalidReserves,
     #[msg("Math overflow")]
     MathOverflow,
+    #[msg("Invalid SNAIL mint")]
+    InvalidSnailMint,
+    #[msg("Invalid USDC LP account")]
+    InvalidUsdcLp,
+    #[msg("Invalid SNAIL LP account")]
+    InvalidSnailLp,
+    #[msg("Hook called outside of a token transfer")]
+    NotTransferring,
+    #[msg("USDC LP account has the wrong mint")]
+    InvalidUsdcLpMint,
+    #[msg("SNAIL LP account has the wrong mint")]
+    InvalidSnailLpMint,
+    #[msg("Invalid TWAP window")]
+    InvalidTwapWindow,
+    #[msg("Not enough observations to cover the TWAP window")]
+    InsufficientObservations,
+    #[msg("Invalid curve steepness")]
+    InvalidCurveSteepness,
+    #[msg("Invalid number of curve steps")]
+    InvalidCurveSteps,
+    #[msg("Invalid curve knots")]
+    InvalidCurveKnots,
+    #[msg("Bounty already configured")]
+    BountyAlreadyConfigured,
+    #[msg("Bounty must be either a fixed amount or a share of at most 100%")]
+    InvalidBounty,
+    #[msg("Invalid bounty mint")]
+    InvalidBountyMint,
+    #[msg("Bounty accounts are required for this game")]
+    MissingBountyAccounts,
+    #[msg("Snail already survived")]
+    AlreadySurvived,
+    #[msg("Game is not over yet")]
+    GameNotOver,
+    #[msg("Snail was breached and must be touched")]
+    PendingBreach,
+    #[msg("Invalid market cap decimals")]
+    InvalidMarketCapDecimals,
+    #[msg("Invalid USDC mint")]
+    InvalidUsdcMint,
+    #[msg("Pool account is required for this price source")]
+    MissingPool,
+    #[msg("Pool account does not match the price source")]
+    InvalidPool,
+    #[msg("Too many pools")]
+    TooManyPools,
+    #[msg("Pool already added")]
+    DuplicatePool,
+    #[msg("Pools cannot change once the transfer hook is set up")]
+    PoolsLocked,
+    #[msg("Excluded account is required for circulating supply")]
+    MissingExcludedAccount,
+    #[msg("Invalid excluded account")]
+    InvalidExcludedAccount,
+    #[msg("Invalid grace period")]
+    InvalidGracePeriod,
+    #[msg("A touch is pending confirmation")]
+    TouchPending,
+    #[msg("No pending touch")]
+    NoPendingTouch,
+    #[msg("Grace period has not passed yet")]
+    GracePeriodActive,
+    #[msg("Market cap too low")]
+    MarketCapTooLow,
+    #[msg("Freeze account is required")]
+    MissingFreezeAccount,
+    #[msg("Invalid freeze account")]
+    InvalidFreezeAccount,
+    #[msg("Invalid outcome")]
+    InvalidOutcome,
+    #[msg("Invalid outcome mint")]
+    InvalidOutcomeMint,
+    #[msg("Outcome vault already configured")]
+    OutcomeVaultAlreadyConfigured,
+    #[msg("Outcome accounts are required")]
+    MissingOutcomeAccounts,
+    #[msg("Invalid penalty token account")]
+    InvalidPenaltyAccount,
+    #[msg("Rewards have not been released")]
+    RewardsNotReleased,
+    #[msg("Invalid reward mint")]
+    InvalidRewardMint,
+    #[msg("Staking is closed")]
+    StakingClosed,
+    #[msg("Nothing new to stake")]
+    NothingToStake,
+    #[msg("Stake is locked until the game ends")]
+    StakeLocked,
+    #[msg("Insufficient stake")]
+    InsufficientStake,
+    #[msg("Reward already claimed")]
+    RewardAlreadyClaimed,
+    #[msg("Snail has not been touched")]
+    GameNotTouched,
+    #[msg("Invalid market cutoff")]
+    InvalidMarketCutoff,
+    #[msg("Market is closed")]
+    MarketClosed,
+    #[msg("Market is not settled")]
+    MarketNotSettled,
+    #[msg("Invalid deposit")]
+    InvalidDeposit,
+    #[msg("A checkpoint was already recorded in this slot")]
+    CheckpointTooSoon,
+    #[msg("Target market cap must be positive")]
+    InvalidTargetMarketCap,
+    #[msg("Game cannot start in the past")]
+    StartInPast,
+    #[msg("SNAIL mint freeze authority must be the freeze authority PDA")]
+    InvalidFreezeAuthority,
+    #[msg("USDC and SNAIL LP accounts must differ")]
+    DuplicateLpAccounts,
+    #[msg("SNAIL mint's transfer hook must point at this program")]
+    InvalidTransferHook,
+    #[msg("Game has already started")]
+    GameStarted,
+    #[msg("Next season already started")]
+    SeasonAlreadyStarted,
+    #[msg("Caller of the pending touch must be passed as pending_toucher")]
+    MissingPendingToucher,
+    #[msg("Toucher token account must belong to the toucher")]
+    InvalidToucherTokenAccount,
+    #[msg("Shell rewards can only be swept after a touch or a survival nobody staked in")]
+    ShellRewardsClaimable,
+    #[msg("The toucher has not pulled the slashed stake yet")]
+    SlashNotPulled,
 }
 
 #[event]
 pub struct SnailTouched {
+    pub game_id: u64,
+    pub current_market_cap: u64,
+    pub required_market_cap: u64,
+    pub toucher: Pubkey,
+    pub bounty_paid: u64,
+}
+
+#[event]
+pub struct SnailTouchPending {
+    pub game_id: u64,
+    pub current_market_cap: u64,
+    pub required_market_cap: u64,
+    pub toucher: Pubkey,
+    pub confirmable_at: i64,
+}
+
+#[event]
+pub struct SnailTouchCleared {
+    pub game_id: u64,
     pub current_market_cap: u64,
     pub required_market_cap: u64,
 }
+
+#[event]
+pub struct SnailBreached {
+    pub game_id: u64,
+    pub current_market_cap: u64,
+    pub required_market_cap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SnailSurvived {
+    pub game_id: u64,
+    pub timestamp: i64,
+    pub freeze_authority_renounced: bool,
+}
+
+#[event]
+pub struct GamePhaseChanged {
+    pub game_id: u64,
+    pub previous_phase: GamePhase,
+    pub phase: GamePhase,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolAdded {
+    pub game_id: u64,
+    pub price_source: PriceSource,
+    pub pool: Pubkey,
+    pub usdc_vault: Pubkey,
+    pub snail_vault: Pubkey,
+}
+
+#[event]
+pub struct SupplyConfigured {
+    pub game_id: u64,
+    pub supply_mode: SupplyMode,
+    pub excluded_accounts: Vec<Pubkey>,
+}
+
+#[event]
+pub struct FreezeAccountsConfigured {
+    pub game_id: u64,
+    pub freeze_accounts: Vec<Pubkey>,
+}
+
+#[event]
+pub struct OutcomeSettled {
+    pub game_id: u64,
+    pub outcome: Outcome,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OutcomeVaultConfigured {
+    pub game_id: u64,
+    pub outcome: Outcome,
+    pub outcome_mint: Pubkey,
+    pub outcome_vault: Pubkey,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub game_id: u64,
+    pub holder: Pubkey,
+    pub burned: u64,
+    pub payout: u64,
+}
+
+#[event]
+pub struct ShellInitialized {
+    pub game_id: u64,
+    pub reward_mint: Pubkey,
+    pub reward_vault: Pubkey,
+}
+
+#[event]
+pub struct Staked {
+    pub game_id: u64,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub game_id: u64,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct ShellRewardClaimed {
+    pub game_id: u64,
+    pub owner: Pubkey,
+    pub payout: u64,
+}
+
+#[event]
+pub struct ShellRewardsSwept {
+    pub game_id: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StakeSlashed {
+    pub game_id: u64,
+    pub owner: Pubkey,
+    pub toucher: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MarketInitialized {
+    pub game_id: u64,
+    pub market_vault: Pubkey,
+    pub cutoff: i64,
+}
+
+#[event]
+pub struct PredictionPlaced {
+    pub game_id: u64,
+    pub owner: Pubkey,
+    pub side: MarketSide,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PredictionClaimed {
+    pub game_id: u64,
+    pub owner: Pubkey,
+    pub payout: u64,
+}
+
+#[event]
+pub struct SeasonStarted {
+    pub game_id: u64,
+    pub previous_game_id: u64,
+    pub season: u32,
+    pub target_market_cap: u64,
+    pub snail_start_stamp: i64,
+    pub snail_end_stamp: i64,
+}
+
+#[event]
+pub struct BountyConfigured {
+    pub game_id: u64,
+    pub bounty_mint: Pubkey,
+    pub bounty_vault: Pubkey,
+    pub amount: u64,
+    pub bps: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint_log() -> CheckpointLog {
+        CheckpointLog {
+            game_state: Pubkey::default(),
+            head: 0,
+            count: 0,
+            last_slot: 0,
+            checkpoints: [Checkpoint::default(); MAX_CHECKPOINTS],
+        }
+    }
+
+    fn observation_buffer() -> ObservationBuffer {
+        ObservationBuffer {
+            game_state: Pubkey::default(),
+            head: 0,
+            count: 0,
+            observations: [Observation::default(); MAX_OBSERVATIONS],
+        }
+    }
+
+    fn checkpoint(timestamp: i64) -> Checkpoint {
+        Checkpoint { timestamp, ..Default::default() }
+    }
+
+    fn timestamps(checkpoints: &[Checkpoint]) -> Vec<i64> {
+        checkpoints.iter().map(|checkpoint| checkpoint.timestamp).collect()
+    }
+
+    #[test]
+    fn checkpoints_come_back_oldest_first() {
+        let mut log = checkpoint_log();
+        assert!(log.latest(10).is_empty());
+
+        for timestamp in 0..3 {
+            log.record(timestamp as u64 + 1, checkpoint(timestamp)).unwrap();
+        }
+        assert_eq!(timestamps(&log.latest(10)), [0, 1, 2]);
+        assert_eq!(timestamps(&log.latest(2)), [1, 2]);
+        assert!(log.latest(0).is_empty());
+
+        // One checkpoint per slot
+        assert!(log.record(3, checkpoint(3)).is_err());
+    }
+
+    #[test]
+    fn checkpoint_log_wraps_around() {
+        let mut log = checkpoint_log();
+        let total = MAX_CHECKPOINTS as i64 + 5;
+        for timestamp in 0..total {
+            log.record(timestamp as u64 + 1, checkpoint(timestamp)).unwrap();
+        }
+
+        assert_eq!(log.count as usize, MAX_CHECKPOINTS);
+        assert_eq!(timestamps(&log.latest(3)), [total - 3, total - 2, total - 1]);
+
+        // Capped at MAX_CHECKPOINTS_RETURNED, still ending with the newest
+        let latest = log.latest(u16::MAX as usize);
+        let expected: Vec<i64> = (total - MAX_CHECKPOINTS_RETURNED as i64..total).collect();
+        assert_eq!(timestamps(&latest), expected);
+    }
+
+    #[test]
+    fn twap_weights_each_market_cap_by_its_duration() {
+        let mut observations = observation_buffer();
+        assert!(observations.twap(0, 0).is_err());
+
+        observations.record(0, 100, 1).unwrap();
+        observations.record(10, 200, 1).unwrap();
+        observations.record(30, 50, 1).unwrap();
+
+        // 100 for 10s, 200 for 20s, 50 for 10s
+        assert_eq!(observations.twap(40, 40).unwrap(), 5_500 / 40);
+        // Starts at the newest observation at or before the window
+        assert_eq!(observations.twap(40, 10).unwrap(), 50);
+        assert_eq!(observations.twap(40, 15).unwrap(), 4_500 / 30);
+        // Not enough history for the window
+        assert!(observations.twap(40, 41).is_err());
+        // Nor for a time before the latest observation
+        assert!(observations.twap(29, 10).is_err());
+    }
+
+    #[test]
+    fn observations_in_one_interval_share_a_slot() {
+        let mut observations = observation_buffer();
+        observations.record(0, 100, 10).unwrap();
+        observations.record(5, 300, 10).unwrap();
+        assert_eq!(observations.count, 1);
+        assert_eq!(observations.latest().unwrap().cumulative_market_cap, 500);
+
+        observations.record(10, 300, 10).unwrap();
+        assert_eq!(observations.count, 2);
+        assert!(observations.record(9, 300, 10).is_err());
+    }
+
+    #[test]
+    fn observation_buffer_wraps_around() {
+        let mut observations = observation_buffer();
+        let total = MAX_OBSERVATIONS as i64 + 10;
+        for timestamp in 0..total {
+            observations.record(timestamp, 7, 1).unwrap();
+        }
+
+        assert_eq!(observations.count as usize, MAX_OBSERVATIONS);
+        assert_eq!(observations.twap(total, MAX_OBSERVATIONS as i64).unwrap(), 7);
+        // The oldest observations were overwritten
+        assert!(observations.twap(total, MAX_OBSERVATIONS as i64 + 2).is_err());
+    }
+
+    #[test]
+    fn sells_into_any_pool_are_recognised() {
+        let game_state = GameState {
+            snail_lp: Pubkey::new_unique(),
+            usdc_lp: Pubkey::new_unique(),
+            pools: vec![PoolConfig {
+                snail_vault: Pubkey::new_unique(),
+                usdc_vault: Pubkey::new_unique(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(game_state.is_pool_snail_vault(&game_state.snail_lp));
+        assert!(game_state.is_pool_snail_vault(&game_state.pools[0].snail_vault));
+        assert!(!game_state.is_pool_snail_vault(&game_state.usdc_lp));
+        assert!(!game_state.is_pool_snail_vault(&game_state.pools[0].usdc_vault));
+        assert!(!game_state.is_pool_snail_vault(&Pubkey::new_unique()));
+    }
+
+    fn finished_game(phase: GamePhase) -> GameState {
+        GameState {
+            game_id: 1,
+            phase,
+            snail_start_stamp: 0,
+            snail_end_stamp: 100,
+            target_market_cap: 1_000,
+            season: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_touched_game_starts_a_next_season() {
+        let touched = finished_game(GamePhase::Touched);
+        let next = touched.next_season(2, 15_000, 200, 50, 150).unwrap();
+
+        assert_eq!(next.game_id, 2);
+        assert_eq!(next.previous_game_id, Some(1));
+        assert_eq!(next.season, 2);
+        assert_eq!(next.survived_seasons, 0);
+        assert_eq!(next.target_market_cap, 1_500);
+        assert_eq!((next.snail_start_stamp, next.snail_end_stamp), (200, 250));
+        assert_eq!(next.phase, GamePhase::Uninitialized);
+        assert_eq!(next.toucher, Pubkey::default());
+
+        let survived = finished_game(GamePhase::Survived);
+        assert_eq!(survived.next_season(2, 15_000, 200, 50, 150).unwrap().survived_seasons, 1);
+    }
+
+    #[test]
+    fn only_one_next_season_of_a_finished_game() {
+        for phase in [GamePhase::Pending, GamePhase::Live, GamePhase::Breached, GamePhase::TouchPending] {
+            assert!(finished_game(phase).next_season(2, 15_000, 200, 50, 150).is_err());
+        }
+
+        let mut touched = finished_game(GamePhase::Touched);
+        touched.next_game_id = Some(2);
+        assert!(touched.next_season(3, 15_000, 200, 50, 150).is_err());
+
+        // Same checks as initialize
+        let touched = finished_game(GamePhase::Touched);
+        assert!(touched.next_season(2, 15_000, 100, 50, 150).is_err());
+        assert!(touched.next_season(2, 0, 200, 50, 150).is_err());
+    }
+}
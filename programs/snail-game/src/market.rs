@@ -0,0 +1,196 @@
+//! Binary prediction market on the result of a game
+//!
+//! Users deposit USDC on `Touched` or `Survived` until the market's cutoff, which
+//! leaves the last tenth of the game window without deposits. No deposit is taken
+//! while the snail is at or below the curve, since a touch in the same transaction
+//! would settle the market at once. The market settles from the game's terminal phase, so no oracle is involved: a
+//! touch settles it at once, survival once `declare_victory` has been called.
+//! Winners share both pools pro rata to their deposits on the winning side. If
+//! nobody backed the winning side, every deposit is refunded.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::{GamePhase, SnailError, BPS};
+
+/// Share of the game window, in bps, after the latest possible cutoff
+pub const CUTOFF_MARGIN_BPS: u64 = 1_000;
+
+/// Side of a prediction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketSide {
+    Touched,
+    Survived,
+}
+
+impl MarketSide {
+    /// Winning side for a game in `phase`, None until the game is over
+    pub fn winner(phase: GamePhase) -> Option<MarketSide> {
+        match phase {
+            GamePhase::Touched => Some(MarketSide::Touched),
+            GamePhase::Survived => Some(MarketSide::Survived),
+            _ => None,
+        }
+    }
+}
+
+/// Prediction market of one game (`["market", game_state]`)
+#[account]
+#[derive(Default)]
+pub struct Market {
+    pub game_state: Pubkey,
+    pub cutoff: i64, // Last time point deposits are accepted, in the game's timing mode
+    pub touched_total: u64,
+    pub survived_total: u64,
+}
+
+impl Market {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // game_state
+        8 + // cutoff
+        8 + // touched_total
+        8; // survived_total
+
+    /// Latest cutoff for a game window, CUTOFF_MARGIN_BPS of it before the end
+    pub fn latest_cutoff(snail_start_stamp: i64, snail_end_stamp: i64) -> i64 {
+        let duration = snail_end_stamp as i128 - snail_start_stamp as i128;
+        let margin = duration * CUTOFF_MARGIN_BPS as i128 / BPS as i128;
+        (snail_end_stamp as i128 - margin) as i64
+    }
+
+    /// Reject a deposit at `time_point` into a game in `phase`
+    /// `market_caps` are the current and required market caps of a live game, as
+    /// `touch_snail` would compare them.
+    pub fn check_deposit(
+        &self,
+        phase: GamePhase,
+        time_point: i64,
+        market_caps: Option<(u128, u128)>,
+    ) -> Result<()> {
+        require!(
+            time_point <= self.cutoff && matches!(phase, GamePhase::Pending | GamePhase::Live),
+            SnailError::MarketClosed
+        );
+        if let Some((current_market_cap, required_market_cap)) = market_caps {
+            require!(current_market_cap > required_market_cap, SnailError::MarketCapTooLow);
+        }
+        Ok(())
+    }
+
+    /// Add `amount` to the pool of `side`
+    pub fn deposit(&mut self, position: &mut Position, side: MarketSide, amount: u64) -> Result<()> {
+        let (total, deposited) = match side {
+            MarketSide::Touched => (&mut self.touched_total, &mut position.touched),
+            MarketSide::Survived => (&mut self.survived_total, &mut position.survived),
+        };
+        *total = total.checked_add(amount).ok_or(SnailError::MathOverflow)?;
+        *deposited = deposited.checked_add(amount).ok_or(SnailError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Amount owed to `position` for a game in `phase`
+    pub fn payout(&self, position: &Position, phase: GamePhase) -> Result<u64> {
+        let winner = MarketSide::winner(phase).ok_or(SnailError::MarketNotSettled)?;
+        let (winning_total, winning_deposit) = match winner {
+            MarketSide::Touched => (self.touched_total, position.touched),
+            MarketSide::Survived => (self.survived_total, position.survived),
+        };
+
+        // Nobody to pay out to: refund every deposit
+        if winning_total == 0 {
+            return Ok(position.touched + position.survived);
+        }
+
+        let pot = self.touched_total as u128 + self.survived_total as u128;
+        let payout = math::mul_div(pot, winning_deposit as u128, winning_total as u128)
+            .ok_or(SnailError::MathOverflow)?;
+        Ok(payout as u64)
+    }
+}
+
+/// One user's deposits in a market (`["position", market, owner]`)
+#[account]
+#[derive(Default)]
+pub struct Position {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub touched: u64,
+    pub survived: u64,
+    pub claimed: bool,
+}
+
+impl Position {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // owner
+        32 + // market
+        8 + // touched
+        8 + // survived
+        1; // claimed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(market: &mut Market, touched: u64, survived: u64) -> Position {
+        let mut position = Position::default();
+        market.deposit(&mut position, MarketSide::Touched, touched).unwrap();
+        market.deposit(&mut position, MarketSide::Survived, survived).unwrap();
+        position
+    }
+
+    #[test]
+    fn unsettled_until_a_terminal_phase() {
+        let mut market = Market::default();
+        let position = position(&mut market, 10, 0);
+        for phase in [GamePhase::Pending, GamePhase::Live, GamePhase::Breached, GamePhase::TouchPending] {
+            assert!(market.payout(&position, phase).is_err());
+        }
+    }
+
+    #[test]
+    fn cutoff_leaves_a_margin_before_the_end() {
+        assert_eq!(Market::latest_cutoff(0, 1_000), 900);
+        assert_eq!(Market::latest_cutoff(1_000, 1_009), 1_009);
+        assert_eq!(Market::latest_cutoff(250_000_000, 250_100_000), 250_090_000);
+    }
+
+    #[test]
+    fn deposits_close_when_a_touch_could_settle_the_market() {
+        let market = Market { cutoff: 100, ..Default::default() };
+        assert!(market.check_deposit(GamePhase::Pending, 0, None).is_ok());
+        assert!(market.check_deposit(GamePhase::Live, 100, Some((501, 500))).is_ok());
+
+        // At or below the curve, a touch in the same transaction would win
+        assert!(market.check_deposit(GamePhase::Live, 50, Some((500, 500))).is_err());
+        assert!(market.check_deposit(GamePhase::Live, 50, Some((0, 500))).is_err());
+        for phase in [GamePhase::Breached, GamePhase::TouchPending, GamePhase::Touched, GamePhase::Survived] {
+            assert!(market.check_deposit(phase, 50, Some((1_000, 500))).is_err());
+        }
+        assert!(market.check_deposit(GamePhase::Live, 101, Some((1_000, 500))).is_err());
+    }
+
+    #[test]
+    fn winners_share_the_pot() {
+        let mut market = Market::default();
+        let alice = position(&mut market, 300, 0);
+        let bob = position(&mut market, 100, 50);
+        let carol = position(&mut market, 0, 550);
+
+        assert_eq!(market.payout(&alice, GamePhase::Touched).unwrap(), 750);
+        assert_eq!(market.payout(&bob, GamePhase::Touched).unwrap(), 250);
+        assert_eq!(market.payout(&carol, GamePhase::Touched).unwrap(), 0);
+
+        assert_eq!(market.payout(&alice, GamePhase::Survived).unwrap(), 0);
+        assert_eq!(market.payout(&bob, GamePhase::Survived).unwrap(), 83);
+        assert_eq!(market.payout(&carol, GamePhase::Survived).unwrap(), 916);
+    }
+
+    #[test]
+    fn refunds_when_nobody_won() {
+        let mut market = Market::default();
+        let alice = position(&mut market, 300, 0);
+        assert_eq!(market.payout(&alice, GamePhase::Survived).unwrap(), 300);
+        assert_eq!(market.payout(&alice, GamePhase::Touched).unwrap(), 300);
+    }
+}
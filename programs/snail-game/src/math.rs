@@ -0,0 +1,334 @@
+//! 1e18 fixed-point math used by the required-cap curve
+//!
+//! All values are unsigned (or signed, for logarithms) integers scaled by `SCALE`,
+//! so `SCALE` represents 1.0. Logarithms are computed in base 2 and converted with
+//! `LN_2` / `LOG2_E`, which keeps every intermediate product inside 128 bits.
+//!
+//! Error bounds, measured against a 50-digit reference over the curve domain
+//! (`base` in [1e-18, 1], `exponent` in [1, 5], `exp2` argument in [-60, 0]):
+//! - `log2`: absolute error below 5e-17 (`ln` adds at most one more unit)
+//! - `exp2`: absolute error below 1e-17 for non-positive arguments
+//! - `pow`: absolute error below 1e-16, and never above 1.0 when `base <= 1.0`
+//! - `pow_interpolated`: absolute error below 1e-16 against the exact interpolation
+//!   of the exact powers
+//!
+//! Results are truncated to 1e-18, so relative error grows as results approach
+//! that resolution.
+
+use anchor_lang::prelude::*;
+
+use crate::SnailError;
+
+/// 1.0 in fixed point
+pub const SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// ln(2) in fixed point
+pub const LN_2: i128 = 693_147_180_559_945_309;
+
+/// log2(e) in fixed point
+pub const LOG2_E: i128 = 1_442_695_040_888_963_407;
+
+/// Number of Taylor terms used by `exp2`; the 20th term is already below 1e-19
+const EXP_TERMS: u128 = 24;
+
+/// Binary logarithm of `x`
+pub fn log2(x: u128) -> Result<i128> {
+    require!(x > 0, SnailError::MathOverflow);
+
+    // log2(x) = -log2(1 / x) keeps the iteration below in [1, 2)
+    if x < SCALE {
+        return Ok(-log2(SCALE * SCALE / x)?);
+    }
+
+    // Integer part: position of the highest bit of x / SCALE
+    let n = 127 - (x / SCALE).leading_zeros();
+    let mut result = (n as i128) * (SCALE as i128);
+
+    // Fractional part: square y in [1, 2) and read one binary digit per round
+    let mut y = x >> n;
+    if y == SCALE {
+        return Ok(result);
+    }
+    let mut delta = (SCALE / 2) as i128;
+    while delta > 0 {
+        y = y * y / SCALE;
+        if y >= 2 * SCALE {
+            result += delta;
+            y >>= 1;
+        }
+        delta >>= 1;
+    }
+
+    Ok(result)
+}
+
+/// Natural logarithm of `x`
+pub fn ln(x: u128) -> Result<i128> {
+    Ok(log2(x)? * LN_2 / (SCALE as i128))
+}
+
+/// 2 raised to the power `x`
+/// Results below 1e-18 round to 0; results above u128 range are an error.
+pub fn exp2(x: i128) -> Result<u128> {
+    let scale = SCALE as i128;
+
+    // Split into integer and fractional parts, with the fraction in [0, 1)
+    let n = x.div_euclid(scale);
+    let f = x.rem_euclid(scale);
+
+    if n < -(SCALE.ilog2() as i128) - 1 {
+        return Ok(0);
+    }
+
+    // 2^f = e^(f * ln 2), summed as a Taylor series (f * ln 2 < 0.7)
+    let y = (f * LN_2 / scale) as u128;
+    let mut term = SCALE;
+    let mut fraction = SCALE;
+    for k in 1..=EXP_TERMS {
+        term = term * y / SCALE / k;
+        if term == 0 {
+            break;
+        }
+        fraction += term;
+    }
+
+    if n < 0 {
+        Ok(fraction >> (-n) as u32)
+    } else {
+        let shift = n as u32;
+        require!(
+            shift < 128 && fraction.leading_zeros() > shift,
+            SnailError::MathOverflow
+        );
+        Ok(fraction << shift)
+    }
+}
+
+/// e raised to the power `x`
+pub fn exp(x: i128) -> Result<u128> {
+    let y = x.checked_mul(LOG2_E).ok_or(SnailError::MathOverflow)? / (SCALE as i128);
+    exp2(y)
+}
+
+/// `base` raised to the fractional power `exponent`, computed as 2^(exponent * log2(base))
+pub fn pow(base: u128, exponent: u128) -> Result<u128> {
+    if base == 0 {
+        return Ok(0);
+    }
+    if exponent == 0 {
+        return Ok(SCALE);
+    }
+    if exponent == SCALE {
+        return Ok(base);
+    }
+
+    let log = log2(base)?;
+    let exponent = i128::try_from(exponent).map_err(|_| SnailError::MathOverflow)?;
+    match log.checked_mul(exponent) {
+        Some(product) => exp2(product / (SCALE as i128)),
+        // Only reachable for |exponent * log2(base)| > 1.7e20, far below 1e-18
+        None if log < 0 => Ok(0),
+        None => err!(SnailError::MathOverflow),
+    }
+}
+
+/// `a * b / c` rounded down, with a 256-bit intermediate product
+/// Returns None when `c` is 0 or the quotient does not fit in 128 bits.
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+
+    // 256-bit product as (hi, lo) from 64-bit limbs
+    let mask = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & mask);
+    let (b_hi, b_lo) = (b >> 64, b & mask);
+    let lo_lo = a_lo * b_lo;
+    let mid_1 = a_hi * b_lo;
+    let mid_2 = a_lo * b_hi;
+    let (mid, mid_carry) = mid_1.overflowing_add(mid_2);
+    let (lo, lo_carry) = lo_lo.overflowing_add(mid << 64);
+    let hi = a_hi * b_hi + (mid >> 64) + ((mid_carry as u128) << 64) + lo_carry as u128;
+
+    if hi == 0 {
+        return Some(lo / c);
+    }
+    if hi >= c {
+        return None;
+    }
+
+    // Long division of (hi, lo) by c, one bit of lo at a time
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> i) & 1);
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1 << i;
+        }
+    }
+
+    Some(quotient)
+}
+
+/// Original curve power (exact copy of Solidity _pow)
+/// The fractional part of the exponent is linearly interpolated between
+/// base^n and base^(n+1), so it only matches `pow` at integer exponents.
+pub fn pow_interpolated(base: u128, exponent: u128) -> Result<u128> {
+    // Handle edge cases
+    if base == 0 {
+        return Ok(0);
+    }
+    if exponent == 0 {
+        return Ok(SCALE);
+    }
+    if exponent == SCALE {
+        return Ok(base);
+    }
+    if base == SCALE {
+        return Ok(SCALE);
+    }
+
+    let integer_part = exponent / SCALE;
+    let fractional_part = exponent % SCALE;
+
+    // Start with base^integerPart
+    let mut result = SCALE;
+    for _ in 0..integer_part {
+        result = result
+            .checked_mul(base)
+            .ok_or(SnailError::MathOverflow)?
+            / SCALE;
+    }
+
+    // For fractional part, use linear interpolation between base^n and base^(n+1)
+    if fractional_part > 0 {
+        let next_power = result
+            .checked_mul(base)
+            .ok_or(SnailError::MathOverflow)?
+            / SCALE;
+        let diff = result
+            .checked_sub(next_power)
+            .ok_or(SnailError::MathOverflow)?;
+        result = result
+            .checked_sub((diff.checked_mul(fractional_part).ok_or(SnailError::MathOverflow)?) / SCALE)
+            .ok_or(SnailError::MathOverflow)?;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Reference values computed with 50 significant digits, rounded to 1e-18
+    const LOG2: [(u128, i128); 8] = [
+        (1, -59_794_705_707_972_522_262),
+        (3, -58_209_743_207_251_366_080),
+        (1_000_000_000, -29_897_352_853_986_261_131),
+        (100_000_000_000_000_000, -3_321_928_094_887_362_348),
+        (300_000_000_000_000_000, -1_736_965_594_166_206_166),
+        (700_000_000_000_000_000, -514_573_172_829_758_240),
+        (999_999_999_999_999_999, -1),
+        (SCALE, 0),
+    ];
+
+    const EXP2: [(i128, u128); 8] = [
+        (-60_000_000_000_000_000_000, 1),
+        (-37_123_456_789_000_000_000, 6_679_223),
+        (-10_000_000_000_000_000_000, 976_562_500_000_000),
+        (-1_000_000_000_000_000_000, 500_000_000_000_000_000),
+        (-500_000_000_000_000_000, 707_106_781_186_547_524),
+        (-123_456_789_123_456_789, 917_985_461_803_960_787),
+        (-1, 999_999_999_999_999_999),
+        (0, SCALE),
+    ];
+
+    const POW: [(u128, u128, u128); 10] = [
+        (1_000_000_000, 1_500_000_000_000_000_000, 31_623),
+        (10_000_000_000_000_000, 2_718_281_828_459_045_235, 3_659_622_955_309),
+        (10_000_000_000_000_000, 3_300_000_000_000_000_000, 251_188_643_151),
+        (250_000_000_000_000_000, 2_718_281_828_459_045_235, 23_090_389_875_362_173),
+        (500_000_000_000_000_000, 1_500_000_000_000_000_000, 353_553_390_593_273_762),
+        (500_000_000_000_000_000, 3_300_000_000_000_000_000, 101_531_549_544_529_440),
+        (900_000_000_000_000_000, 2_718_281_828_459_045_235, 750_962_490_040_042_290),
+        (900_000_000_000_000_000, 5_000_000_000_000_000_000, 590_490_000_000_000_000),
+        (999_000_000_000_000_000, 1_500_000_000_000_000_000, 998_500_375_062_523_449),
+        (999_000_000_000_000_000, 3_300_000_000_000_000_000, 996_703_793_355_623_355),
+    ];
+
+    const POW_INTERPOLATED: [(u128, u128, u128); 8] = [
+        (1_000_000_000, 1_500_000_000_000_000_000, 500_000_000),
+        (10_000_000_000_000_000, 2_718_281_828_459_045_235, 28_890_098_982_555),
+        (250_000_000_000_000_000, 3_300_000_000_000_000_000, 12_109_375_000_000_000),
+        (500_000_000_000_000_000, 2_718_281_828_459_045_235, 160_214_771_442_619_346),
+        (900_000_000_000_000_000, 2_718_281_828_459_045_235, 751_819_171_894_817_336),
+        (900_000_000_000_000_000, 5_000_000_000_000_000_000, 590_490_000_000_000_000),
+        (999_000_000_000_000_000, 2_718_281_828_459_045_235, 997_284_154_016_916_044),
+        (999_000_000_000_000_000, 3_300_000_000_000_000_000, 996_703_898_100_300_000),
+    ];
+
+    #[test]
+    fn log2_matches_reference() {
+        for (x, expected) in LOG2 {
+            let actual = log2(x).unwrap();
+            assert!((actual - expected).abs() < 50, "log2({x}) = {actual}, expected {expected}");
+        }
+        assert!(log2(0).is_err());
+    }
+
+    #[test]
+    fn exp2_matches_reference() {
+        for (x, expected) in EXP2 {
+            let actual = exp2(x).unwrap();
+            assert!(actual.abs_diff(expected) < 10, "exp2({x}) = {actual}, expected {expected}");
+        }
+        assert_eq!(exp2(-61_000_000_000_000_000_000).unwrap(), 0);
+        assert!(exp2(128_000_000_000_000_000_000).is_err());
+    }
+
+    #[test]
+    fn pow_matches_reference() {
+        for (base, exponent, expected) in POW {
+            let actual = pow(base, exponent).unwrap();
+            assert!(
+                actual.abs_diff(expected) < 100,
+                "pow({base}, {exponent}) = {actual}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn pow_interpolated_matches_reference() {
+        for (base, exponent, expected) in POW_INTERPOLATED {
+            let actual = pow_interpolated(base, exponent).unwrap();
+            assert!(
+                actual.abs_diff(expected) < 100,
+                "pow_interpolated({base}, {exponent}) = {actual}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn mul_div_uses_full_precision() {
+        let q64 = 1u128 << 64;
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), Some(u128::MAX));
+        assert_eq!(mul_div(q64 * 3, q64 * 5, q64), Some(q64 * 15));
+        assert_eq!(mul_div(u128::MAX, 2, 3), Some(u128::MAX / 3 * 2));
+        assert_eq!(mul_div(u128::MAX, 2, 1), None);
+        assert_eq!(mul_div(1, 1, 0), None);
+    }
+
+    proptest! {
+        #[test]
+        fn pow_never_exceeds_one_for_bases_up_to_one(
+            base in 1..=SCALE,
+            exponent in SCALE..=5 * SCALE,
+        ) {
+            prop_assert!(pow(base, exponent)? <= SCALE);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! What a successful touch does to the game
+//!
+//! Every game picks an `Outcome` at `initialize`. `Freeze` is the original
+//! behaviour; the others settle the game's outcome vault (`["outcome_vault",
+//! game_state]`) instead. Each handler here does the bookkeeping for its outcome
+//! and returns the amount the caller moves out of the vault, so the token
+//! program calls stay in `touch_snail` and the arithmetic can be tested on its own.
+//! Whatever the outcome, the game keeps the freeze authority for its next season.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::{GameState, SnailError};
+
+/// Consequence of a touch, chosen at `initialize`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Outcome {
+    /// Freeze the pools and the configured freeze accounts
+    #[default]
+    Freeze,
+    /// Burn everything held in the outcome vault
+    BurnTreasury,
+    /// Let SNAIL holders claim the outcome vault pro rata by burning SNAIL
+    ReleaseRewards,
+    /// Send the outcome vault to `recipient`
+    TransferPenalty { recipient: Pubkey },
+}
+
+impl Outcome {
+    pub const LEN: usize = 1 + 32;
+
+    pub fn validate(&self) -> Result<()> {
+        if let Outcome::TransferPenalty { recipient } = self {
+            require!(*recipient != Pubkey::default(), SnailError::InvalidOutcome);
+        }
+        Ok(())
+    }
+
+    /// Whether the outcome settles an outcome vault
+    pub fn uses_vault(&self) -> bool {
+        *self != Outcome::Freeze
+    }
+
+    /// Owner of the account receiving the penalty, Pubkey::default() otherwise
+    pub fn penalty_recipient(&self) -> Pubkey {
+        match self {
+            Outcome::TransferPenalty { recipient } => *recipient,
+            _ => Pubkey::default(),
+        }
+    }
+}
+
+/// Record the burn of the treasury stash, returning the amount to burn
+pub fn burn_treasury(game_state: &mut GameState, vault_balance: u64) -> Result<u64> {
+    require!(
+        game_state.outcome == Outcome::BurnTreasury,
+        SnailError::InvalidOutcome
+    );
+
+    game_state.outcome_amount = vault_balance;
+    Ok(vault_balance)
+}
+
+/// Open reward claims against the vault balance, shared over `snail_supply`
+/// Nothing leaves the vault here; holders call `claim_reward` afterwards.
+pub fn release_rewards(
+    game_state: &mut GameState,
+    vault_balance: u64,
+    snail_supply: u64,
+) -> Result<u64> {
+    require!(
+        game_state.outcome == Outcome::ReleaseRewards,
+        SnailError::InvalidOutcome
+    );
+
+    game_state.outcome_amount = vault_balance;
+    game_state.reward_supply = snail_supply;
+    Ok(0)
+}
+
+/// Record the penalty, returning the amount to send to the recipient
+pub fn transfer_penalty(game_state: &mut GameState, vault_balance: u64) -> Result<u64> {
+    require!(
+        matches!(game_state.outcome, Outcome::TransferPenalty { .. }),
+        SnailError::InvalidOutcome
+    );
+
+    game_state.outcome_amount = vault_balance;
+    Ok(vault_balance)
+}
+
+/// Reward paid for burning `burned` SNAIL once rewards are released
+pub fn reward_payout(game_state: &GameState, burned: u64) -> Result<u64> {
+    require!(
+        game_state.outcome == Outcome::ReleaseRewards && game_state.reward_supply > 0,
+        SnailError::RewardsNotReleased
+    );
+
+    let payout = math::mul_div(
+        game_state.outcome_amount as u128,
+        burned as u128,
+        game_state.reward_supply as u128,
+    )
+    .ok_or(SnailError::MathOverflow)?;
+    Ok(payout.min(game_state.outcome_amount as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GamePhase;
+
+    fn game(outcome: Outcome) -> GameState {
+        GameState {
+            outcome,
+            phase: GamePhase::Touched,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn penalty_needs_a_recipient() {
+        assert!(Outcome::TransferPenalty { recipient: Pubkey::default() }.validate().is_err());
+        assert!(Outcome::TransferPenalty { recipient: Pubkey::new_unique() }.validate().is_ok());
+        assert!(Outcome::Freeze.validate().is_ok());
+        assert!(!Outcome::Freeze.uses_vault());
+        assert!(Outcome::BurnTreasury.uses_vault());
+    }
+
+    #[test]
+    fn burn_treasury_burns_the_whole_vault() {
+        let mut game_state = game(Outcome::BurnTreasury);
+        assert_eq!(burn_treasury(&mut game_state, 5_000).unwrap(), 5_000);
+        assert_eq!(game_state.outcome_amount, 5_000);
+
+        let mut game_state = game(Outcome::Freeze);
+        assert!(burn_treasury(&mut game_state, 5_000).is_err());
+    }
+
+    #[test]
+    fn release_rewards_moves_nothing_and_opens_claims() {
+        let mut game_state = game(Outcome::ReleaseRewards);
+        assert!(reward_payout(&game_state, 1).is_err());
+
+        assert_eq!(release_rewards(&mut game_state, 1_000, 4_000).unwrap(), 0);
+        assert_eq!(game_state.outcome_amount, 1_000);
+        assert_eq!(game_state.reward_supply, 4_000);
+
+        // A quarter of the supply claims a quarter of the rewards
+        assert_eq!(reward_payout(&game_state, 1_000).unwrap(), 250);
+        assert_eq!(reward_payout(&game_state, 3).unwrap(), 0);
+        assert_eq!(reward_payout(&game_state, 4_000).unwrap(), 1_000);
+        assert_eq!(reward_payout(&game_state, u64::MAX).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn release_rewards_with_large_balances() {
+        let mut game_state = game(Outcome::ReleaseRewards);
+        release_rewards(&mut game_state, u64::MAX, u64::MAX).unwrap();
+        assert_eq!(reward_payout(&game_state, u64::MAX / 2).unwrap(), u64::MAX / 2);
+    }
+
+    #[test]
+    fn transfer_penalty_sends_the_whole_vault() {
+        let recipient = Pubkey::new_unique();
+        let mut game_state = game(Outcome::TransferPenalty { recipient });
+        assert_eq!(transfer_penalty(&mut game_state, 7).unwrap(), 7);
+        assert_eq!(game_state.outcome_amount, 7);
+        assert_eq!(game_state.outcome.penalty_recipient(), recipient);
+
+        let mut game_state = game(Outcome::ReleaseRewards);
+        assert!(transfer_penalty(&mut game_state, 7).is_err());
+        assert_eq!(game_state.outcome.penalty_recipient(), Pubkey::default());
+    }
+}
@@ -0,0 +1,645 @@
+//! Spot price adapters for the pool a game is priced from
+//!
+//! Every adapter returns the price of one raw SNAIL unit in raw USDC units as an
+//! exact ratio: reserve-based pools give the reserves themselves, concentrated
+//! liquidity pools a Q64.64 price over `Q64`. Pool accounts are parsed by offset
+//! from each AMM's published account layout, so the program does not depend on
+//! the AMM crates.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::SnailError;
+
+/// 1.0 in Q64.64
+pub const Q64: u128 = 1 << 64;
+
+/// Where the SNAIL price is read from, chosen at `initialize`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PriceSource {
+    /// Ratio of the two LP token account balances (constant-product pools)
+    #[default]
+    TokenAccounts,
+    /// Raydium CPMM pool state: vault balances net of accrued fees
+    RaydiumCpmm,
+    /// Orca Whirlpool: `sqrt_price` of the pool
+    OrcaWhirlpool,
+    /// Meteora DLMM pair: price of the active bin
+    MeteoraDlmm,
+}
+
+/// Raw USDC per raw SNAIL, as `numerator / denominator`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Price {
+    pub numerator: u128,
+    pub denominator: u128,
+}
+
+impl Price {
+    /// Market cap of `supply` raw SNAIL, in units of 10^-market_cap_decimals USDC
+    pub fn market_cap(&self, supply: u64, usdc_decimals: u8, market_cap_decimals: u8) -> u128 {
+        // Scaling down after the floor division gives the same result as one exact division
+        let (scale_up, scale_down) = if market_cap_decimals >= usdc_decimals {
+            (10u128.pow((market_cap_decimals - usdc_decimals) as u32), 1)
+        } else {
+            (1, 10u128.pow((usdc_decimals - market_cap_decimals) as u32))
+        };
+
+        math::mul_div(supply as u128 * scale_up, self.numerator, self.denominator)
+            .map_or(u128::MAX, |market_cap| market_cap / scale_down)
+    }
+}
+
+/// A pool vault as the game sees it
+#[derive(Clone, Copy, Debug)]
+pub struct Vault {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// One SNAIL/USDC pool: its vaults and, if the source reads one, its state account
+#[derive(Clone, Copy, Debug)]
+pub struct PoolSnapshot<'a> {
+    pub usdc_vault: Vault,
+    pub snail_vault: Vault,
+    pub pool: Option<(Pubkey, &'a [u8])>, // Owner and data of the pool state account
+}
+
+impl PriceSource {
+    /// Program that must own the pool account, if the source reads one
+    pub fn program_id(&self) -> Option<Pubkey> {
+        match self {
+            PriceSource::TokenAccounts => None,
+            PriceSource::RaydiumCpmm => Some(raydium_cpmm::ID),
+            PriceSource::OrcaWhirlpool => Some(orca_whirlpool::ID),
+            PriceSource::MeteoraDlmm => Some(meteora_dlmm::ID),
+        }
+    }
+
+    /// Price of SNAIL in USDC
+    /// `pool` is the owner and data of the pool account; the pool's vaults must be
+    /// `usdc_vault` and `snail_vault`. Returns None when the pool has no liquidity.
+    pub fn price(
+        &self,
+        pool: Option<(&Pubkey, &[u8])>,
+        usdc_vault: &Vault,
+        snail_vault: &Vault,
+    ) -> Result<Option<Price>> {
+        let Some(program_id) = self.program_id() else {
+            return Ok(reserve_price(usdc_vault.amount, snail_vault.amount));
+        };
+
+        let (owner, data) = pool.ok_or(SnailError::MissingPool)?;
+        require_keys_eq!(*owner, program_id, SnailError::InvalidPool);
+
+        match self {
+            PriceSource::TokenAccounts => unreachable!(),
+            PriceSource::RaydiumCpmm => raydium_cpmm::price(data, usdc_vault, snail_vault),
+            PriceSource::OrcaWhirlpool => orca_whirlpool::price(data, usdc_vault, snail_vault),
+            PriceSource::MeteoraDlmm => meteora_dlmm::price(data, usdc_vault, snail_vault),
+        }
+    }
+}
+
+/// Average of `(value, weight)` pairs, or None when there are none
+/// When every weight is 0 the values count equally.
+pub fn weighted_average(values: &[(u128, u128)]) -> Option<u128> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let total_weight: u128 = values.iter().map(|(_, weight)| weight).sum();
+    let weighted = |(value, weight): &(u128, u128)| match total_weight {
+        0 => value / values.len() as u128,
+        _ => math::mul_div(*value, *weight, total_weight).unwrap_or(u128::MAX),
+    };
+
+    Some(values.iter().map(weighted).fold(0, u128::saturating_add))
+}
+
+/// Market cap of `supply` from `(price, weight)` pairs, one per pool with
+/// liquidity, or None when there are none
+pub fn weighted_market_cap(
+    prices: &[(Price, u128)],
+    supply: u64,
+    usdc_decimals: u8,
+    market_cap_decimals: u8,
+) -> Option<u128> {
+    let market_caps: Vec<_> = prices
+        .iter()
+        .map(|(price, weight)| {
+            let market_cap = price.market_cap(supply, usdc_decimals, market_cap_decimals);
+            (market_cap, *weight)
+        })
+        .collect();
+
+    weighted_average(&market_caps)
+}
+
+/// Constant-product price from two reserves
+fn reserve_price(usdc_reserve: u64, snail_reserve: u64) -> Option<Price> {
+    if snail_reserve == 0 {
+        return None;
+    }
+    Some(Price {
+        numerator: usdc_reserve as u128,
+        denominator: snail_reserve as u128,
+    })
+}
+
+/// Check the Anchor discriminator and minimum length of a pool account
+fn check_account(data: &[u8], discriminator: &[u8; 8], len: usize) -> Result<()> {
+    require!(
+        data.len() >= len && data[..8] == discriminator[..],
+        SnailError::InvalidPool
+    );
+    Ok(())
+}
+
+/// Whether the pool's (mint, vault) pair `a` is SNAIL and `b` is USDC, or the reverse
+fn snail_is_first(
+    a: (Pubkey, Pubkey),
+    b: (Pubkey, Pubkey),
+    usdc_vault: &Vault,
+    snail_vault: &Vault,
+) -> Result<bool> {
+    let usdc = (usdc_vault.mint, usdc_vault.address);
+    let snail = (snail_vault.mint, snail_vault.address);
+
+    if (a, b) == (snail, usdc) {
+        Ok(true)
+    } else if (a, b) == (usdc, snail) {
+        Ok(false)
+    } else {
+        err!(SnailError::InvalidPool)
+    }
+}
+
+/// Q64.64 price, or its inverse
+fn x64_price(price_x64: u128, inverse: bool) -> Option<Price> {
+    if price_x64 == 0 {
+        return None;
+    }
+    Some(if inverse {
+        Price { numerator: Q64, denominator: price_x64 }
+    } else {
+        Price { numerator: price_x64, denominator: Q64 }
+    })
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Raydium CPMM `PoolState`
+pub mod raydium_cpmm {
+    use super::*;
+
+    pub const ID: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+    pub const DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+    pub const LEN: usize = 637;
+
+    pub const TOKEN_0_VAULT: usize = 72;
+    pub const TOKEN_1_VAULT: usize = 104;
+    pub const TOKEN_0_MINT: usize = 168;
+    pub const TOKEN_1_MINT: usize = 200;
+    pub const PROTOCOL_FEES_TOKEN_0: usize = 341;
+    pub const PROTOCOL_FEES_TOKEN_1: usize = 349;
+    pub const FUND_FEES_TOKEN_0: usize = 357;
+    pub const FUND_FEES_TOKEN_1: usize = 365;
+    pub const CREATOR_FEES_TOKEN_0: usize = 397;
+    pub const CREATOR_FEES_TOKEN_1: usize = 405;
+
+    /// (mint, vault) of token 0 and token 1
+    pub fn tokens(data: &[u8]) -> Result<[(Pubkey, Pubkey); 2]> {
+        check_account(data, &DISCRIMINATOR, LEN)?;
+        Ok([
+            (read_pubkey(data, TOKEN_0_MINT), read_pubkey(data, TOKEN_0_VAULT)),
+            (read_pubkey(data, TOKEN_1_MINT), read_pubkey(data, TOKEN_1_VAULT)),
+        ])
+    }
+
+    /// Vault balances minus the protocol, fund and creator fees they still hold
+    pub fn price(data: &[u8], usdc_vault: &Vault, snail_vault: &Vault) -> Result<Option<Price>> {
+        let [token_0, token_1] = tokens(data)?;
+        let snail_is_token_0 = snail_is_first(token_0, token_1, usdc_vault, snail_vault)?;
+
+        let fees_0 = read_u64(data, PROTOCOL_FEES_TOKEN_0)
+            .saturating_add(read_u64(data, FUND_FEES_TOKEN_0))
+            .saturating_add(read_u64(data, CREATOR_FEES_TOKEN_0));
+        let fees_1 = read_u64(data, PROTOCOL_FEES_TOKEN_1)
+            .saturating_add(read_u64(data, FUND_FEES_TOKEN_1))
+            .saturating_add(read_u64(data, CREATOR_FEES_TOKEN_1));
+        let (snail_fees, usdc_fees) = if snail_is_token_0 {
+            (fees_0, fees_1)
+        } else {
+            (fees_1, fees_0)
+        };
+
+        Ok(reserve_price(
+            usdc_vault.amount.saturating_sub(usdc_fees),
+            snail_vault.amount.saturating_sub(snail_fees),
+        ))
+    }
+}
+
+/// Orca `Whirlpool`
+pub mod orca_whirlpool {
+    use super::*;
+
+    pub const ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+    pub const DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+    pub const LEN: usize = 653;
+
+    pub const LIQUIDITY: usize = 49;
+    pub const SQRT_PRICE: usize = 65;
+    pub const TOKEN_MINT_A: usize = 101;
+    pub const TOKEN_VAULT_A: usize = 133;
+    pub const TOKEN_MINT_B: usize = 181;
+    pub const TOKEN_VAULT_B: usize = 213;
+
+    /// (mint, vault) of token A and token B
+    pub fn tokens(data: &[u8]) -> Result<[(Pubkey, Pubkey); 2]> {
+        check_account(data, &DISCRIMINATOR, LEN)?;
+        Ok([
+            (read_pubkey(data, TOKEN_MINT_A), read_pubkey(data, TOKEN_VAULT_A)),
+            (read_pubkey(data, TOKEN_MINT_B), read_pubkey(data, TOKEN_VAULT_B)),
+        ])
+    }
+
+    /// `sqrt_price` is sqrt(B per A) in Q64.64, so the price is its square
+    pub fn price(data: &[u8], usdc_vault: &Vault, snail_vault: &Vault) -> Result<Option<Price>> {
+        let [token_a, token_b] = tokens(data)?;
+        let snail_is_a = snail_is_first(token_a, token_b, usdc_vault, snail_vault)?;
+
+        // Without in-range liquidity the price can be moved for free
+        if read_u128(data, LIQUIDITY) == 0 {
+            return Ok(None);
+        }
+
+        let sqrt_price = read_u128(data, SQRT_PRICE);
+        let b_per_a = math::mul_div(sqrt_price, sqrt_price, Q64).ok_or(SnailError::MathOverflow)?;
+
+        Ok(x64_price(b_per_a, !snail_is_a))
+    }
+}
+
+/// Meteora DLMM `LbPair`
+pub mod meteora_dlmm {
+    use super::*;
+
+    pub const ID: Pubkey = pubkey!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
+    pub const DISCRIMINATOR: [u8; 8] = [33, 11, 49, 98, 181, 101, 177, 13];
+    pub const LEN: usize = 904;
+
+    pub const ACTIVE_ID: usize = 76;
+    pub const BIN_STEP: usize = 80;
+    pub const TOKEN_X_MINT: usize = 88;
+    pub const TOKEN_Y_MINT: usize = 120;
+    pub const RESERVE_X: usize = 152;
+    pub const RESERVE_Y: usize = 184;
+
+    /// (mint, reserve) of token X and token Y
+    pub fn tokens(data: &[u8]) -> Result<[(Pubkey, Pubkey); 2]> {
+        check_account(data, &DISCRIMINATOR, LEN)?;
+        Ok([
+            (read_pubkey(data, TOKEN_X_MINT), read_pubkey(data, RESERVE_X)),
+            (read_pubkey(data, TOKEN_Y_MINT), read_pubkey(data, RESERVE_Y)),
+        ])
+    }
+
+    /// The active bin's price is (1 + bin_step / 10000)^active_id, Y per X
+    pub fn price(data: &[u8], usdc_vault: &Vault, snail_vault: &Vault) -> Result<Option<Price>> {
+        let [token_x, token_y] = tokens(data)?;
+        let snail_is_x = snail_is_first(token_x, token_y, usdc_vault, snail_vault)?;
+
+        if usdc_vault.amount == 0 && snail_vault.amount == 0 {
+            return Ok(None);
+        }
+
+        let active_id = read_i32(data, ACTIVE_ID);
+        let bin_step = read_u16(data, BIN_STEP) as u128;
+        let base = Q64 + bin_step * Q64 / 10_000;
+
+        // Y per X is base^|id| for a non-negative id and its inverse otherwise;
+        // SNAIL is priced by the power itself when exactly one of those flips
+        let power = pow_x64(base, active_id.unsigned_abs());
+        let inverse = snail_is_x != (active_id >= 0);
+        match (power, inverse) {
+            (Some(power), inverse) => Ok(x64_price(power, inverse)),
+            // A power beyond Q64.64 only fits as a price once inverted, where it rounds to 0
+            (None, true) => Ok(Some(Price { numerator: 0, denominator: 1 })),
+            (None, false) => err!(SnailError::MathOverflow),
+        }
+    }
+
+    /// `base^exponent` in Q64.64 by repeated squaring, None on overflow
+    fn pow_x64(base: u128, exponent: u32) -> Option<u128> {
+        let mut result = Q64;
+        let mut square = base;
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = math::mul_div(result, square, Q64)?;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                square = math::mul_div(square, square, Q64)?;
+            }
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNAIL_MINT: Pubkey = Pubkey::new_from_array([1; 32]);
+    const USDC_MINT: Pubkey = Pubkey::new_from_array([2; 32]);
+    const SNAIL_VAULT: Pubkey = Pubkey::new_from_array([3; 32]);
+    const USDC_VAULT: Pubkey = Pubkey::new_from_array([4; 32]);
+
+    fn vaults(usdc_amount: u64, snail_amount: u64) -> (Vault, Vault) {
+        (
+            Vault { address: USDC_VAULT, mint: USDC_MINT, amount: usdc_amount },
+            Vault { address: SNAIL_VAULT, mint: SNAIL_MINT, amount: snail_amount },
+        )
+    }
+
+    /// Pool account written field by field in the order of the AMM's own account
+    /// struct, so the adapters' offsets are checked rather than reused
+    struct Layout(Vec<u8>);
+
+    impl Layout {
+        fn new(discriminator: [u8; 8]) -> Self {
+            Layout(discriminator.to_vec())
+        }
+
+        fn bytes(mut self, bytes: &[u8]) -> Self {
+            self.0.extend_from_slice(bytes);
+            self
+        }
+
+        fn key(self, key: Pubkey) -> Self {
+            self.bytes(key.as_ref())
+        }
+
+        /// Fields the adapters ignore, filled with non-zero bytes
+        fn skip(self, len: usize) -> Self {
+            self.bytes(&vec![0xA5; len])
+        }
+
+        fn finish(self, len: usize) -> Vec<u8> {
+            assert_eq!(self.0.len(), len);
+            self.0
+        }
+    }
+
+    /// (mint, vault) of SNAIL and USDC, in pool order
+    fn ordered(snail_first: bool) -> [(Pubkey, Pubkey); 2] {
+        let snail = (SNAIL_MINT, SNAIL_VAULT);
+        let usdc = (USDC_MINT, USDC_VAULT);
+        if snail_first {
+            [snail, usdc]
+        } else {
+            [usdc, snail]
+        }
+    }
+
+    /// Raydium CPMM `PoolState`; fees are (protocol, fund, creator) per token
+    fn raydium_pool(snail_first: bool, snail_fees: [u64; 3], usdc_fees: [u64; 3]) -> Vec<u8> {
+        let [(mint_0, vault_0), (mint_1, vault_1)] = ordered(snail_first);
+        let (fees_0, fees_1) = if snail_first { (snail_fees, usdc_fees) } else { (usdc_fees, snail_fees) };
+        Layout::new(raydium_cpmm::DISCRIMINATOR)
+            .skip(32) // amm_config
+            .skip(32) // pool_creator
+            .key(vault_0) // token_0_vault
+            .key(vault_1) // token_1_vault
+            .skip(32) // lp_mint
+            .key(mint_0) // token_0_mint
+            .key(mint_1) // token_1_mint
+            .skip(32 * 2) // token_0_program, token_1_program
+            .skip(32) // observation_key
+            .skip(5) // auth_bump, status, lp_mint_decimals, mint_0_decimals, mint_1_decimals
+            .skip(8) // lp_supply
+            .bytes(&fees_0[0].to_le_bytes()) // protocol_fees_token_0
+            .bytes(&fees_1[0].to_le_bytes()) // protocol_fees_token_1
+            .bytes(&fees_0[1].to_le_bytes()) // fund_fees_token_0
+            .bytes(&fees_1[1].to_le_bytes()) // fund_fees_token_1
+            .skip(8 * 2) // open_time, recent_epoch
+            .skip(2 + 6) // creator_fee_on, enable_creator_fee, padding1
+            .bytes(&fees_0[2].to_le_bytes()) // creator_fees_token_0
+            .bytes(&fees_1[2].to_le_bytes()) // creator_fees_token_1
+            .skip(8 * 28) // padding
+            .finish(raydium_cpmm::LEN)
+    }
+
+    /// Orca `Whirlpool`
+    fn whirlpool(snail_is_a: bool, sqrt_price: u128, liquidity: u128) -> Vec<u8> {
+        let [(mint_a, vault_a), (mint_b, vault_b)] = ordered(snail_is_a);
+        Layout::new(orca_whirlpool::DISCRIMINATOR)
+            .skip(32) // whirlpools_config
+            .skip(1 + 2 + 2) // whirlpool_bump, tick_spacing, fee_tier_index_seed
+            .skip(2 + 2) // fee_rate, protocol_fee_rate
+            .bytes(&liquidity.to_le_bytes()) // liquidity
+            .bytes(&sqrt_price.to_le_bytes()) // sqrt_price
+            .skip(4) // tick_current_index
+            .skip(8 * 2) // protocol_fee_owed_a, protocol_fee_owed_b
+            .key(mint_a) // token_mint_a
+            .key(vault_a) // token_vault_a
+            .skip(16) // fee_growth_global_a
+            .key(mint_b) // token_mint_b
+            .key(vault_b) // token_vault_b
+            .skip(16) // fee_growth_global_b
+            .skip(8) // reward_last_updated_timestamp
+            .skip(3 * (32 * 3 + 16 * 2)) // reward_infos
+            .finish(orca_whirlpool::LEN)
+    }
+
+    /// Meteora DLMM `LbPair`
+    fn lb_pair(snail_is_x: bool, active_id: i32, bin_step: u16) -> Vec<u8> {
+        let [(mint_x, reserve_x), (mint_y, reserve_y)] = ordered(snail_is_x);
+        Layout::new(meteora_dlmm::DISCRIMINATOR)
+            .skip(32) // parameters
+            .skip(32) // v_parameters
+            .skip(1) // bump_seed
+            .bytes(&bin_step.to_le_bytes()) // bin_step_seed
+            .skip(1) // pair_type
+            .bytes(&active_id.to_le_bytes()) // active_id
+            .bytes(&bin_step.to_le_bytes()) // bin_step
+            .skip(1 + 1 + 2) // status, require_base_factor_seed, base_factor_seed
+            .skip(1 + 1) // activation_type, creator_pool_on_off_control
+            .key(mint_x) // token_x_mint
+            .key(mint_y) // token_y_mint
+            .key(reserve_x) // reserve_x
+            .key(reserve_y) // reserve_y
+            .skip(16) // protocol_fee
+            .skip(32) // padding1
+            .skip(2 * (32 * 3 + 8 * 2 + 16 + 8 * 2)) // reward_infos
+            .skip(32) // oracle
+            .skip(8 * 16) // bin_array_bitmap
+            .skip(8) // last_updated_at
+            .skip(32) // padding2
+            .skip(32 * 2) // pre_activation_swap_address, base_key
+            .skip(8 * 2) // activation_point, pre_activation_duration
+            .skip(8 + 8) // padding3, padding4
+            .skip(32) // creator
+            .skip(2) // token_mint_x_program_flag, token_mint_y_program_flag
+            .skip(22) // reserved
+            .finish(meteora_dlmm::LEN)
+    }
+
+    fn price(source: PriceSource, data: &[u8], usdc_amount: u64, snail_amount: u64) -> Result<Option<Price>> {
+        let (usdc_vault, snail_vault) = vaults(usdc_amount, snail_amount);
+        let owner = source.program_id().unwrap_or_default();
+        source.price(Some((&owner, data)), &usdc_vault, &snail_vault)
+    }
+
+    fn price_of(source: PriceSource, data: &[u8]) -> Option<Price> {
+        price(source, data, 1, 1).unwrap()
+    }
+
+    fn as_f64(price: Price) -> f64 {
+        price.numerator as f64 / price.denominator as f64
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            ((actual - expected) / expected).abs() < 1e-12,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn token_accounts_match_reserve_ratio() {
+        let (usdc_vault, snail_vault) = vaults(50_000_000_000, 400_000_000_000_000);
+        let price = PriceSource::TokenAccounts
+            .price(None, &usdc_vault, &snail_vault)
+            .unwrap()
+            .unwrap();
+
+        // 50k USDC (6 decimals) against 400M SNAIL (6 decimals), 1B supply
+        let supply = 1_000_000_000_000_000;
+        assert_eq!(price.market_cap(supply, 6, 0), 125_000);
+        assert_eq!(price.market_cap(supply, 6, 6), 125_000_000_000);
+        assert_eq!(price.market_cap(supply, 6, 9), 125_000_000_000_000);
+
+        let (usdc_vault, snail_vault) = vaults(50_000_000_000, 0);
+        assert_eq!(
+            PriceSource::TokenAccounts.price(None, &usdc_vault, &snail_vault).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn thin_pools_barely_move_the_average() {
+        // 1M market cap in a pool holding 100k USDC, 10M in one holding 100 USDC
+        let average = weighted_average(&[(1_000_000, 100_000), (10_000_000, 100)]).unwrap();
+        assert_eq!(average, 999_000 + 9_990);
+
+        assert_eq!(weighted_average(&[(1_000, 0), (3_000, 0)]), Some(2_000));
+        assert_eq!(weighted_average(&[]), None);
+    }
+
+    #[test]
+    fn adapters_decode_mints_and_vaults() {
+        for snail_first in [true, false] {
+            let expected = ordered(snail_first);
+            assert_eq!(raydium_cpmm::tokens(&raydium_pool(snail_first, [0; 3], [0; 3])).unwrap(), expected);
+            assert_eq!(orca_whirlpool::tokens(&whirlpool(snail_first, Q64, 1)).unwrap(), expected);
+            assert_eq!(meteora_dlmm::tokens(&lb_pair(snail_first, 0, 25)).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn raydium_cpmm_nets_out_fees() {
+        for snail_first in [true, false] {
+            let data = raydium_pool(snail_first, [50_000, 30_000, 20_000], [1_000_000, 600_000, 400_000]);
+            let price = price(PriceSource::RaydiumCpmm, &data, 2_002_000_000, 1_000_100_000)
+                .unwrap()
+                .unwrap();
+            assert_eq!(as_f64(price), 2.0);
+        }
+    }
+
+    #[test]
+    fn whirlpool_squares_sqrt_price() {
+        // SNAIL as token A: sqrt(0.0004 USDC per SNAIL)
+        let price = price_of(PriceSource::OrcaWhirlpool, &whirlpool(true, Q64 / 50, 1)).unwrap();
+        assert_close(as_f64(price), 0.0004);
+
+        // USDC as token A: sqrt(2500 SNAIL per USDC)
+        let price = price_of(PriceSource::OrcaWhirlpool, &whirlpool(false, Q64 * 50, 1)).unwrap();
+        assert_close(as_f64(price), 0.0004);
+
+        // No in-range liquidity
+        assert_eq!(price_of(PriceSource::OrcaWhirlpool, &whirlpool(true, Q64 / 50, 0)), None);
+    }
+
+    #[test]
+    fn dlmm_prices_the_active_bin() {
+        let bin_step = 25;
+        for active_id in [-5_000, -1, 0, 1, 1_234, 10_000] {
+            let expected = (1.0 + bin_step as f64 / 10_000.0).powi(active_id);
+
+            let price = price_of(PriceSource::MeteoraDlmm, &lb_pair(true, active_id, bin_step)).unwrap();
+            assert_close(as_f64(price), expected);
+
+            let price = price_of(PriceSource::MeteoraDlmm, &lb_pair(false, active_id, bin_step)).unwrap();
+            assert_close(as_f64(price), 1.0 / expected);
+        }
+
+        // Far below Q64.64 resolution
+        let tiny = price_of(PriceSource::MeteoraDlmm, &lb_pair(true, -400_000, 100)).unwrap();
+        assert_eq!(tiny.numerator, 0);
+
+        // Far above it
+        assert!(price(PriceSource::MeteoraDlmm, &lb_pair(true, 400_000, 100), 1, 1).is_err());
+    }
+
+    #[test]
+    fn rejects_foreign_accounts() {
+        let data = whirlpool(true, Q64, 1);
+        let (usdc_vault, snail_vault) = vaults(1, 1);
+
+        // Wrong owner
+        let owner = raydium_cpmm::ID;
+        assert!(PriceSource::OrcaWhirlpool
+            .price(Some((&owner, &data)), &usdc_vault, &snail_vault)
+            .is_err());
+
+        // Wrong layout
+        assert!(price(PriceSource::RaydiumCpmm, &data, 1, 1).is_err());
+
+        // Truncated account
+        assert!(price(PriceSource::OrcaWhirlpool, &data[..200], 1, 1).is_err());
+
+        // Missing pool account
+        assert!(PriceSource::OrcaWhirlpool.price(None, &usdc_vault, &snail_vault).is_err());
+
+        // Pool for a different vault
+        let other_vault = Vault { address: Pubkey::new_unique(), ..snail_vault };
+        let owner = orca_whirlpool::ID;
+        assert!(PriceSource::OrcaWhirlpool
+            .price(Some((&owner, &data)), &usdc_vault, &other_vault)
+            .is_err());
+    }
+}
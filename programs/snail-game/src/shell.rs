@@ -0,0 +1,213 @@
+//! Defender staking ("the shell")
+//!
+//! Holders lock SNAIL in a per-user stake vault (`["stake_vault", stake]`) while the
+//! game runs. Stake accrues weight in SNAIL-seconds (SNAIL-slots for slot-timed
+//! games) between `snail_start_stamp` and `snail_end_stamp`. If the game survives,
+//! stakers split the shell's reward vault by weight. If the snail is touched, every
+//! stake is slashed to the toucher and the reward vault goes back to the game owner.
+//! Withdrawals that were approved but not yet pulled are not part of the stake.
+//!
+//! The program never transfers SNAIL itself (see `routes_through_this_program`).
+//! Deposits are plain transfers into the vault picked up by `stake`. Withdrawals and
+//! slashes approve the recipient as the vault's delegate, and the recipient pulls
+//! the tokens.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+use crate::{GameState, SnailError};
+
+/// Staking pool of one game (`["shell", game_state]`)
+#[account]
+#[derive(Default)]
+pub struct Shell {
+    pub game_state: Pubkey,
+    pub reward_mint: Pubkey,
+    pub total_staked: u64,
+    pub total_stake_seconds: u128, // Weight of every stake, up to last_update
+    pub last_update: i64,
+    pub reward_total: u64, // Reward vault balance when the first reward was claimed
+    pub rewards_settled: bool,
+}
+
+impl Shell {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // game_state
+        32 + // reward_mint
+        8 + // total_staked
+        16 + // total_stake_seconds
+        8 + // last_update
+        8 + // reward_total
+        1; // rewards_settled
+
+    /// Bring the total weight up to `time_point`
+    pub fn accrue(&mut self, game_state: &GameState, time_point: i64) -> Result<()> {
+        self.total_stake_seconds = self
+            .total_stake_seconds
+            .checked_add(stake_seconds(game_state, self.total_staked, self.last_update, time_point))
+            .ok_or(SnailError::MathOverflow)?;
+        self.last_update = self.last_update.max(time_point);
+        Ok(())
+    }
+
+    /// Reward owed to `stake`, fixing the reward total on the first claim
+    /// Both accounts must be accrued to the end of the game.
+    pub fn reward_payout(&mut self, stake: &Stake, reward_vault_balance: u64) -> Result<u64> {
+        if !self.rewards_settled {
+            self.reward_total = reward_vault_balance;
+            self.rewards_settled = true;
+        }
+        if self.total_stake_seconds == 0 {
+            return Ok(0);
+        }
+
+        let payout = math::mul_div(
+            self.reward_total as u128,
+            stake.stake_seconds,
+            self.total_stake_seconds,
+        )
+        .ok_or(SnailError::MathOverflow)?;
+        Ok(payout as u64)
+    }
+}
+
+/// One holder's stake in a game (`["stake", game_state, owner]`)
+#[account]
+#[derive(Default)]
+pub struct Stake {
+    pub owner: Pubkey,
+    pub game_state: Pubkey,
+    pub amount: u64,
+    pub stake_seconds: u128, // Weight up to last_update
+    pub last_update: i64,
+    pub reward_claimed: bool,
+}
+
+impl Stake {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // owner
+        32 + // game_state
+        8 + // amount
+        16 + // stake_seconds
+        8 + // last_update
+        1; // reward_claimed
+
+    /// Bring the stake's weight up to `time_point`
+    pub fn accrue(&mut self, game_state: &GameState, time_point: i64) -> Result<()> {
+        self.stake_seconds = self
+            .stake_seconds
+            .checked_add(stake_seconds(game_state, self.amount, self.last_update, time_point))
+            .ok_or(SnailError::MathOverflow)?;
+        self.last_update = self.last_update.max(time_point);
+        Ok(())
+    }
+
+    /// Take the whole stake out of `shell`, returning the amount slashed
+    /// Withdrawals still waiting in the vault are no longer staked and stay out.
+    pub fn slash(&mut self, shell: &mut Shell) -> Result<u64> {
+        require!(self.amount > 0, SnailError::InsufficientStake);
+        let slashed = self.amount;
+        shell.total_staked -= slashed;
+        self.amount = 0;
+        Ok(slashed)
+    }
+
+    /// Withdrawals not yet pulled from a stake vault holding `vault_balance`
+    pub fn pending_withdrawal(&self, vault_balance: u64) -> u64 {
+        vault_balance.saturating_sub(self.amount)
+    }
+}
+
+/// Weight of `amount` held from `from` to `to`, counting only the game window
+pub fn stake_seconds(game_state: &GameState, amount: u64, from: i64, to: i64) -> u128 {
+    let from = from.clamp(game_state.snail_start_stamp, game_state.snail_end_stamp);
+    let to = to.clamp(game_state.snail_start_stamp, game_state.snail_end_stamp);
+    if to <= from {
+        return 0;
+    }
+    amount as u128 * (to - from) as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GamePhase;
+
+    const START: i64 = 1_700_000_000;
+    const END: i64 = START + 1_000;
+
+    fn game() -> GameState {
+        GameState {
+            snail_start_stamp: START,
+            snail_end_stamp: END,
+            phase: GamePhase::Live,
+            ..Default::default()
+        }
+    }
+
+    /// Stake `amount` at `timestamp`, as the handler does
+    fn stake(shell: &mut Shell, stake: &mut Stake, amount: u64, timestamp: i64) {
+        let game_state = game();
+        shell.accrue(&game_state, timestamp).unwrap();
+        stake.accrue(&game_state, timestamp).unwrap();
+        shell.total_staked += amount;
+        stake.amount += amount;
+    }
+
+    #[test]
+    fn only_the_game_window_counts() {
+        let game_state = game();
+        assert_eq!(stake_seconds(&game_state, 10, START - 500, START), 0);
+        assert_eq!(stake_seconds(&game_state, 10, START - 500, START + 100), 1_000);
+        assert_eq!(stake_seconds(&game_state, 10, END - 100, END + 500), 1_000);
+        assert_eq!(stake_seconds(&game_state, 10, END, END + 500), 0);
+        assert_eq!(stake_seconds(&game_state, 10, START + 200, START + 100), 0);
+    }
+
+    #[test]
+    fn rewards_follow_time_weighted_stake() {
+        let mut shell = Shell::default();
+        let mut early = Stake::default();
+        let mut late = Stake::default();
+
+        // Same amount, staked for the whole game and for its second half
+        stake(&mut shell, &mut early, 100, START - 10);
+        stake(&mut shell, &mut late, 100, START + 500);
+
+        let game_state = game();
+        shell.accrue(&game_state, END + 10).unwrap();
+        early.accrue(&game_state, END + 10).unwrap();
+        late.accrue(&game_state, END + 10).unwrap();
+        assert_eq!(shell.total_stake_seconds, early.stake_seconds + late.stake_seconds);
+
+        assert_eq!(shell.reward_payout(&early, 3_000).unwrap(), 2_000);
+        // The reward total is fixed by the first claim
+        assert_eq!(shell.reward_payout(&late, 1_000).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn slashing_leaves_pending_withdrawals_to_the_owner() {
+        let mut shell = Shell::default();
+        let mut stake_account = Stake::default();
+        stake(&mut shell, &mut stake_account, 150, START - 10);
+        // Unstake 50 before the start, without pulling it
+        shell.total_staked -= 50;
+        stake_account.amount -= 50;
+        assert_eq!(stake_account.pending_withdrawal(150), 50);
+
+        assert_eq!(stake_account.slash(&mut shell).unwrap(), 100);
+        assert_eq!((shell.total_staked, stake_account.amount), (0, 0));
+        // Once the toucher has pulled the slash, the withdrawal is what is left
+        assert_eq!(stake_account.pending_withdrawal(50), 50);
+        // Nothing left to slash
+        assert!(stake_account.slash(&mut shell).is_err());
+    }
+
+    #[test]
+    fn no_rewards_without_weight() {
+        let mut shell = Shell::default();
+        let mut stake_account = Stake::default();
+        stake(&mut shell, &mut stake_account, 100, END + 1);
+        assert_eq!(shell.reward_payout(&stake_account, 3_000).unwrap(), 0);
+    }
+}